@@ -80,7 +80,7 @@ const raffleSchema = new mongoose.Schema({
   // Status
   status: {
     type: String,
-    enum: ['active', 'completed', 'cancelled'],
+    enum: ['active', 'completed', 'cancelled', 'failed'],
     default: 'active',
     index: true
   },
@@ -93,6 +93,12 @@ const raffleSchema = new mongoose.Schema({
   winnerTicketIndex: {
     type: Number
   },
+  // Every awarded prize of a multi-prize raffle, in prize order
+  prizeWinners: [{
+    prize: Number,
+    winner: String,
+    ticketIndex: Number
+  }],
   
   // Randomness details
   drandRound: {
@@ -100,7 +106,7 @@ const raffleSchema = new mongoose.Schema({
   },
   endReason: {
     type: String,
-    enum: ['time', 'soldout']
+    enum: ['time', 'soldout', 'min_tickets']
   },
   
   // Blockchain tracking
@@ -237,23 +237,11 @@ async function syncRaffleParticipantsFromBlockchain(raffleId) {
     const raffleInfo = await Raffle.findOne({ raffleId: raffleId });
     const ticketPrice = raffleInfo ? raffleInfo.ticketPrice : 1;
     
-    // Aggregate tickets by address since contract returns individual tickets as (address, 1) pairs
-    const participantMap = new Map();
-    
-    // Process each ticket entry from the contract
-    for (const [address, ticketCount] of contractResult.participants || []) {
-      const addr = address.toString();
-      if (participantMap.has(addr)) {
-        participantMap.set(addr, participantMap.get(addr) + ticketCount);
-      } else {
-        participantMap.set(addr, ticketCount);
-      }
-    }
-    
-    logger.debug(`📊 Aggregated ${participantMap.size} unique participants from ${contractResult.participants?.length || 0} ticket entries`);
+    // The contract already returns one aggregated entry per buyer
+    const participants = contractResult.participants || [];
     
-    // Insert aggregated participants into MongoDB
-    for (const [address, totalTickets] of participantMap.entries()) {
+    // Insert participants into MongoDB
+    for (const { address, ticket_count: totalTickets } of participants) {
       const participantData = {
         raffleId: raffleId,
         address: address,
@@ -275,7 +263,7 @@ async function syncRaffleParticipantsFromBlockchain(raffleId) {
       logger.debug(`💾 Saved participant ${address}: ${totalTickets} tickets, ${participantData.totalPaid} total`);
     }
     
-    logger.debug(`✅ Synced ${participantMap.size} participants for raffle ${raffleId}`);
+    logger.debug(`✅ Synced ${participants.length} participants for raffle ${raffleId}`);
   } catch (error) {
     logger.error(`❌ Failed to sync participants for raffle ${raffleId}:`, error);
   }
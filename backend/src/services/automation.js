@@ -158,9 +158,38 @@ class AutomationService {
         logger.info(`✅ Perfect alignment: Contract and MongoDB both show ${contractTotalSold} tickets sold`);
       }
       
-      // Step 2: Get fresh drand randomness (exactly like Ubuntu command)
-      logger.info('🎲 Fetching drand randomness...');
-      const drandData = await this.getDrandRandomness();
+      // Raffles that missed min_tickets are never drawn; expiring them opens refunds
+      const minTickets = contractRaffle.raffle.min_tickets || 0;
+      if (contractTotalSold < minTickets) {
+        if (new Date() < raffle.endTime) {
+          logger.info(`⏭️ Raffle ${raffle.raffleId} is below min_tickets (${contractTotalSold}/${minTickets}) but still open, skipping`);
+          return;
+        }
+        logger.info(`🚫 Raffle ${raffle.raffleId} sold ${contractTotalSold}/${minTickets} tickets, expiring...`);
+        const result = await blockchainService.executeContract(
+          blockchainService.automationAddress,
+          { expire_raffle: { raffle_id: parseInt(raffle.raffleId) } }
+        );
+        await this.updateRaffleAfterEnding(raffle.raffleId, {
+          status: 'failed',
+          endTxHash: result.transactionHash,
+          endReason: 'min_tickets'
+        });
+        return;
+      }
+      
+      // Commit-reveal raffles are drawn by their creator's reveal, not by a beacon
+      if (contractRaffle.raffle.randomness_source !== 'drand') {
+        logger.info(`⏭️ Raffle ${raffle.raffleId} uses commit-reveal randomness, skipping`);
+        return;
+      }
+      
+      // Step 2: Get the drand beacon for the round the contract pinned at close,
+      // from the chain the raffle was pinned to
+      const targetRound = contractRaffle.raffle.drand_round;
+      const chainHash = contractRaffle.raffle.drand_chain;
+      logger.info(`🎲 Fetching drand randomness for round ${targetRound} of chain ${chainHash}...`);
+      const drandData = await this.getDrandRandomness(chainHash, targetRound);
       if (!drandData) {
         throw new Error('Failed to get drand randomness');
       }
@@ -173,7 +202,8 @@ class AutomationService {
           raffle_id: parseInt(raffle.raffleId),
           drand_round: parseInt(drandData.round.toString()),
           randomness: drandData.randomness.toString(),
-          signature: drandData.signature.toString()
+          signature: drandData.signature.toString(),
+          previous_signature: drandData.previousSignature ? drandData.previousSignature.toString() : null
         }
       };
       
@@ -193,32 +223,22 @@ class AutomationService {
         logger.info(`✅ Successfully ended raffle ${raffle.raffleId}!`);
         logger.info(`📄 Transaction Hash: ${result.transactionHash}`);
         
-        // Step 5: Look up the actual winner address from the contract
-        logger.info(`🔍 Looking up winner address from contract...`);
-        const updatedRaffleInfo = await blockchainService.getRaffleInfo(parseInt(raffle.raffleId));
-        
-        if (!updatedRaffleInfo.raffle) {
-          throw new Error(`Failed to get updated raffle info for raffle ${raffle.raffleId}`);
-        }
-        
-        const winnerAddress = updatedRaffleInfo.raffle.winner;
-        if (!winnerAddress) {
-          throw new Error(`No winner found in contract for raffle ${raffle.raffleId}`);
+        // Step 5: Read the winners the contract drew from the transaction's events
+        const prizeWinners = this.parsePrizeWinners(result.events);
+        if (prizeWinners.length === 0) {
+          throw new Error(`No prize_winner events found for raffle ${raffle.raffleId}`);
         }
+        const { winner: winnerAddress, ticketIndex: winningTicketIndex } = prizeWinners[0];
+        logger.info(`🏆 Winners from contract events: ${prizeWinners.map(w => `prize ${w.prize} → ${w.winner} (ticket #${w.ticketIndex + 1})`).join(', ')}`);
         
-        logger.info(`🏆 Winner address from contract: ${winnerAddress}`);
-        
-        // Step 6: Calculate winning ticket index using EXACT contract total_sold value
-        const winningTicketIndex = this.calculateWinningTicketIndex(drandData.randomness, contractTotalSold);
-        logger.info(`🎲 Calculated winning ticket index: ${winningTicketIndex} (ticket #${winningTicketIndex + 1}) using contract total_sold: ${contractTotalSold}`);
-        
-        // Step 7: Update MongoDB with results including winner address
+        // Step 6: Update MongoDB with results including winner address
         await this.updateRaffleAfterEnding(raffle.raffleId, {
           status: 'completed', // Ensure status is updated
           endTxHash: result.transactionHash,
           drandRound: drandData.round,
           winnerTicketIndex: winningTicketIndex,
           winner: winnerAddress,
+          prizeWinners,
           endReason: contractTotalSold >= raffle.maxTickets ? 'soldout' : 'time'
         });
         
@@ -268,33 +288,18 @@ class AutomationService {
     }
   }
 
-  // Calculate winning ticket index using the same logic as the smart contract
-  calculateWinningTicketIndex(randomness, totalSold) {
-    try {
-      // Convert hex randomness to bytes (same as contract)
-      const rndBytes = Buffer.from(randomness, 'hex');
-      if (rndBytes.length === 0) {
-        throw new Error('Empty randomness');
-      }
-      
-      // Use first 8 bytes (same as contract)
-      const slice = rndBytes.length >= 8 ? rndBytes.slice(0, 8) : rndBytes;
-      const u64buf = Buffer.alloc(8);
-      slice.copy(u64buf, 0);
-      
-      // Convert to u64 big endian (same as contract)
-      const seed = u64buf.readBigUInt64BE(0);
-      
-      // Calculate winner index: seed % total_sold (same as contract)
-      const winnerIndex = Number(seed % BigInt(totalSold));
-      
-      logger.debug(`Randomness: ${randomness.substring(0, 16)}..., Seed: ${seed}, Total: ${totalSold}, Winner: ${winnerIndex}`);
-      
-      return winnerIndex;
-    } catch (error) {
-      logger.error('Error calculating winning ticket index:', error);
-      throw error;
-    }
+  // Winners as emitted by the contract, one `prize_winner` attribute per awarded
+  // prize formatted `<prize>:<winner>:<ticket_index>` (see contract::winning_ticket)
+  parsePrizeWinners(events = []) {
+    return events
+      .filter(event => event.type === 'wasm')
+      .flatMap(event => event.attributes)
+      .filter(attr => attr.key === 'prize_winner')
+      .map(attr => {
+        const [prize, winner, ticketIndex] = attr.value.split(':');
+        return { prize: parseInt(prize), winner, ticketIndex: parseInt(ticketIndex) };
+      })
+      .sort((a, b) => a.prize - b.prize);
   }
 
   // Update raffle in MongoDB after successful ending
@@ -330,11 +335,10 @@ class AutomationService {
     }
   }
 
-  async getDrandRandomness() {
+  async getDrandRandomness(chainHash, round) {
     try {
-      // Get the latest drand beacon
-      // Using the League of Entropy mainnet
-      const response = await axios.get('https://api.drand.sh/public/latest', {
+      // Get the beacon for the given round (not yet available until it is published)
+      const response = await axios.get(`https://api.drand.sh/${chainHash}/public/${round}`, {
         timeout: 10000
       });
       
@@ -342,7 +346,8 @@ class AutomationService {
         return {
           round: response.data.round,
           randomness: response.data.randomness,
-          signature: response.data.signature
+          signature: response.data.signature,
+          previousSignature: response.data.previous_signature
         };
       } else {
         throw new Error('Invalid drand response format');
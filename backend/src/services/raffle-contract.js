@@ -74,19 +74,26 @@ class RaffleContractService {
         await this.connect();
       }
 
-      const queryMsg = { 
-        participants: { 
-          raffle_id: raffleId,
-          start_after: null,
-          limit: limit
-        } 
-      };
-      
+      // One entry per buyer, paged by address
       logger.debug(`🔍 Querying participants for raffle ${raffleId}...`);
-      const result = await this.client.queryContractSmart(this.contractAddress, queryMsg);
-      logger.debug(`✅ Found ${result.participants?.length || 0} participants for raffle ${raffleId}`);
+      const participants = [];
+      let startAfter = null;
+      for (;;) {
+        const queryMsg = {
+          participants: {
+            raffle_id: raffleId,
+            start_after: startAfter,
+            limit: limit
+          }
+        };
+        const page = await this.client.queryContractSmart(this.contractAddress, queryMsg);
+        participants.push(...(page.participants || []));
+        if (!page.participants || page.participants.length < limit) break;
+        startAfter = page.participants[page.participants.length - 1].address;
+      }
+      logger.debug(`✅ Found ${participants.length} participants for raffle ${raffleId}`);
       
-      return result;
+      return { raffle_id: raffleId, participants };
     } catch (error) {
       logger.error(`❌ Failed to fetch participants for raffle ${raffleId}:`, error);
       throw error;
@@ -1,33 +1,63 @@
  use cosmwasm_std::{
-    entry_point, to_json_binary, Addr, BankMsg, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdError, StdResult, Timestamp, Uint128, WasmMsg
+    entry_point, to_json_binary, Addr, Binary, Attribute, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, Response,
+    StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, Uint256, WasmMsg
 };
-use bls12_381::{G1Affine, G2Affine};
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt};
 use sha2::{Sha256, Digest};
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, RaffleListResponse, RaffleResponse, RaffleView, ParticipantResponse, ParticipantsResponse, ConfigResponse, CreateRaffleNftMsg, BuyTicketsCw20Msg};
+use crate::error::ContractError;
+use crate::migrations;
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RaffleListResponse, RaffleResponse, RaffleView, ParticipantResponse, ParticipantsResponse, ParticipantEntry, TicketOwnerResponse, TicketsOfResponse, OwnedTicketRange, UserRafflesResponse, UserRaffleEntry, UserWinsResponse, UserWin, ConfigResponse, RefundResponse, CollectedFee, CollectedFeesResponse, CreateRaffleNftMsg, BuyTicketsCw20Msg, DrandChainMsg, DrandChainResponse, DrandChainsResponse, OwnershipResponse, SeriesPotResponse, DrawResponse, VerifyDrawResponse};
 use cw_storage_plus::Bound;
 use serde_json_wasm;
-use crate::state::{Config, CONFIG, Raffle, RAFFLES, RaffleStatus, NEXT_ID, TICKETS, USER_TICKET_COUNT, USED_ROUNDS};
+use cw20::Cw20ExecuteMsg;
+use crate::state::{Config, CONFIG, Raffle, RAFFLES, RaffleStatus, NEXT_ID, TICKET_RANGES, TicketRange, USER_TICKET_COUNT, USED_ROUNDS, REFUNDS_CLAIMED, COLLECTED_FEES, FeeBalance, DrandChain, EscrowStatus, DrandScheme, DRAND_CHAINS, Prize, PrizeWinner, Pot, SERIES_POTS, DrawRecord, DRAWS, DrawProof, RandomnessSource, ENTROPY, status_key, USER_RAFFLES, USER_WINS, OWNER_TICKET_RANGES, OperatorRole, OPERATORS};
+
+#[cfg(test)]
+mod tests;
 
 const CONTRACT_NAME: &str = "coreum-raffle";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Bounds the transfers and draws a single EndRaffle has to make
+const MAX_PRIZES: usize = 10;
+// Redraws for a ticket whose owner already won, before falling back to a scan
+const MAX_REDRAWS: u32 = 16;
+// Domain tag of winner derivation, bump the version if the algorithm changes
+const WINNER_DOMAIN: &[u8] = b"coreum-raffle/winner/v1";
+// The executor bounty takes at most this share of a raffle's ticket sales
+const MAX_BOUNTY_BPS: u128 = 500;
+// Reply id of prize transfers, whose failure must not block settlement or refunds
+const PRIZE_TRANSFER_REPLY_ID: u64 = 1;
+
 #[entry_point]
-pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> Result<Response, ContractError> {
+    ensure_fee_bps(msg.protocol_fee_bps)?;
     let admin = msg
         .admin
         .map(|a| deps.api.addr_validate(&a))
         .transpose()? // Option<Result> -> Result<Option>
         .unwrap_or(info.sender.clone());
 
+    for chain in msg.drand_chains {
+        let chain = validate_drand_chain(chain)?;
+        DRAND_CHAINS.save(deps.storage, &chain.chain_hash, &chain)?;
+    }
+    let default_drand_chain = msg.default_drand_chain
+        .map(|hash| load_active_drand_chain(deps.as_ref(), &hash).map(|c| c.chain_hash))
+        .transpose()?;
+
     let cfg = Config {
-        admin,
+        admin: Some(admin),
+        pending_admin: None,
         protocol_fee_bps: msg.protocol_fee_bps,
         bounty_amount: msg.bounty_amount,
-        drand_pubkey: msg.drand_pubkey,
-        drand_round_seconds: None,
+        default_drand_chain,
+        paused: false,
     };
     CONFIG.save(deps.storage, &cfg)?;
     NEXT_ID.save(deps.storage, &1u64)?;
@@ -36,17 +66,88 @@ pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: Instantiate
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract { contract: stored.contract });
+    }
+    let from = parse_version(&stored.version)?;
+    let to = parse_version(CONTRACT_VERSION)?;
+    if from > to {
+        return Err(ContractError::CannotDowngrade { stored: stored.version, current: CONTRACT_VERSION.to_string() });
+    }
+
+    // Ordered: each step upgrades storage written by the version before it
+    let mut resp = Response::new();
+    if from < Version::new(0, 2, 0) {
+        resp = resp.add_messages(migrations::v0_2_0(deps.branch(), &env, &msg)?);
+    }
+    if from < Version::new(0, 3, 0) {
+        migrations::v0_3_0(deps.branch())?;
+    }
+    if from < Version::new(0, 4, 0) {
+        migrations::v0_4_0(deps.branch())?;
+    }
+    if from < Version::new(0, 5, 0) {
+        migrations::v0_5_0(deps.branch())?;
+    }
+    if from < Version::new(0, 6, 0) {
+        migrations::v0_6_0(deps.branch())?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(resp
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(v: &str) -> Result<Version, ContractError> {
+    Version::parse(v).map_err(|_| ContractError::InvalidVersion { version: v.to_string() })
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::ReceiveNft(msg) => exec_receive_nft(deps, env, info, msg),
         ExecuteMsg::Receive(msg) => exec_receive_cw20(deps, env, info, msg),
-        ExecuteMsg::CreateRaffle { nft_contract, token_id, price, max_tickets, start_time, end_time, revenue_address, payment_cw20 }
-            => exec_create_raffle(deps, env, info, nft_contract, token_id, price, max_tickets, start_time, end_time, revenue_address, payment_cw20),
-        ExecuteMsg::BuyTickets { raffle_id, count } => exec_buy_tickets(deps, env, info, raffle_id, count),
-        ExecuteMsg::EndRaffle { raffle_id, drand_round, randomness, signature } => exec_end_raffle(deps, env, info, raffle_id, drand_round, randomness, signature),
+        ExecuteMsg::CreateRaffle { nft_contract, token_id, price, max_tickets, min_tickets, max_tickets_per_wallet, max_tickets_per_tx, start_time, end_time, revenue_address, payment_cw20, drand_chain, randomness_source }
+            => exec_create_raffle(deps, env, info, nft_contract, token_id, CreateRaffleNftMsg { price, max_tickets, min_tickets, max_tickets_per_wallet, max_tickets_per_tx, start_time, end_time, revenue_address, payment_cw20, drand_chain, randomness_source }),
+        ExecuteMsg::CreateCoinRaffle(params) => {
+            let prize = Prize::Coins { amount: info.funds };
+            create_raffle(deps, env, info.sender, vec![prize], None, params)
+        }
+        ExecuteMsg::CreateLottery { raffle, tiers_bps, series } => {
+            // The pot comes from ticket sales, so funds sent here would be stranded
+            cw_utils::nonpayable(&info)?;
+            let pot = Pot { tiers_bps, series, rollover: Uint128::zero() };
+            create_raffle(deps, env, info.sender, vec![], Some(pot), raffle)
+        }
+        ExecuteMsg::AddCoinPrize { raffle_id } => exec_add_prize(deps, env, info.sender, raffle_id, Prize::Coins { amount: info.funds }),
+        ExecuteMsg::AddNftPrize { raffle_id, nft_contract, token_id } => exec_add_nft_prize(deps, env, info, raffle_id, nft_contract, token_id),
+        ExecuteMsg::AddCw20Prize { raffle_id, token, amount } => exec_add_cw20_prize(deps, env, info, raffle_id, token, amount),
+        ExecuteMsg::BuyTickets { raffle_id, count, entropy } => exec_buy_tickets(deps, env, info, raffle_id, count, entropy),
+        ExecuteMsg::EndRaffle { raffle_id, drand_round, randomness, signature, previous_signature } => exec_end_raffle(deps, env, info, raffle_id, drand_round, randomness, signature, previous_signature),
+        ExecuteMsg::RevealRandomness { raffle_id, secret } => exec_reveal_randomness(deps, env, info, raffle_id, secret),
         ExecuteMsg::CancelRaffle { raffle_id } => exec_cancel_raffle(deps, env, info, raffle_id),
-        ExecuteMsg::UpdateConfig { fee_bps, bounty, drand_pubkey, drand_round_seconds } => exec_update_config(deps, info, fee_bps, bounty, drand_pubkey, drand_round_seconds),
-        ExecuteMsg::WithdrawFees { to: _to } => exec_withdraw_fees(deps, info),
+        ExecuteMsg::ExpireRaffle { raffle_id } => exec_expire_raffle(deps, env, info, raffle_id),
+        ExecuteMsg::ClaimRefund { raffle_id } => exec_claim_refund(deps, env, info, raffle_id),
+        ExecuteMsg::UpdateConfig { fee_bps, bounty, default_drand_chain } => exec_update_config(deps, info, fee_bps, bounty, default_drand_chain),
+        ExecuteMsg::AddDrandChain { chain } => exec_add_drand_chain(deps, info, chain),
+        ExecuteMsg::SetDrandChainActive { chain_hash, active } => exec_set_drand_chain_active(deps, info, chain_hash, active),
+        ExecuteMsg::WithdrawFees { to, denoms, amounts } => exec_withdraw_fees(deps, info, to, denoms, amounts),
+        ExecuteMsg::ProposeAdmin { address } => exec_propose_admin(deps, info, address),
+        ExecuteMsg::AcceptAdmin {} => exec_accept_admin(deps, info),
+        ExecuteMsg::RenounceAdmin {} => exec_renounce_admin(deps, info),
+        ExecuteMsg::SetOperator { role, address, enabled } => exec_set_operator(deps, info, role, address, enabled),
+        ExecuteMsg::SetPaused { paused } => exec_set_paused(deps, info, paused),
+        ExecuteMsg::Reindex { limit } => {
+            let (processed, done) = migrations::reindex(deps, limit.unwrap_or(100).clamp(1, 500))?;
+            Ok(Response::new()
+                .add_attribute("action", "reindex")
+                .add_attribute("processed", processed.to_string())
+                .add_attribute("done", done.to_string()))
+        }
     }
 }
 
@@ -55,22 +156,108 @@ fn exec_receive_nft(
     env: Env,
     info: MessageInfo,
     msg: cw721::Cw721ReceiveMsg,
-) -> StdResult<Response> {
-    let hook: CreateRaffleNftMsg = serde_json_wasm::from_slice(&msg.msg).map_err(|_| StdError::generic_err("invalid msg"))?;
+) -> Result<Response, ContractError> {
     let original_sender = deps.api.addr_validate(&msg.sender)?;
-    exec_create_raffle(
-        deps,
-        env,
-        MessageInfo { sender: original_sender, funds: vec![] },
-        info.sender.to_string(),
-        msg.token_id,
-        hook.price,
-        hook.max_tickets,
-        hook.start_time,
-        hook.end_time,
-        hook.revenue_address,
-        hook.payment_cw20,
-    )
+    // The cw721 contract only sends this hook after transferring the token to us
+    let prize = Prize::Nft { contract: info.sender, token_id: msg.token_id };
+    // Only creation: the hook's sender is whatever the cw721 contract claims, so it
+    // can't authorize adding to an existing raffle (see AddNftPrize)
+    let hook: CreateRaffleNftMsg = serde_json_wasm::from_slice(&msg.msg).map_err(|_| ContractError::InvalidHookMsg {})?;
+    let resp = create_raffle(deps, env, original_sender, vec![prize], None, hook)?;
+    Ok(resp.add_attribute("escrow", "deposit"))
+}
+
+/// Pulls a further NFT prize from the creator, which requires a prior cw721 approval
+fn exec_add_nft_prize(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    raffle_id: u64,
+    nft_contract: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    cw_utils::nonpayable(&info)?;
+    let nft_addr = deps.api.addr_validate(&nft_contract)?;
+    ensure_nft_approved(deps.as_ref(), &env, &info.sender, &nft_addr, &token_id)?;
+    let pull_msg = transfer_nft_msg(&nft_addr, &env.contract.address, &token_id)?;
+    let prize = Prize::Nft { contract: nft_addr, token_id };
+    let resp = exec_add_prize(deps, env, info.sender, raffle_id, prize)?;
+    Ok(resp.add_message(pull_msg))
+}
+
+/// Pulls cw20 tokens from the creator as the next prize, which requires a prior allowance
+fn exec_add_cw20_prize(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    raffle_id: u64,
+    token: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    cw_utils::nonpayable(&info)?;
+    let token_addr = deps.api.addr_validate(&token)?;
+    let pull_msg = WasmMsg::Execute {
+        contract_addr: token_addr.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+            owner: info.sender.to_string(),
+            recipient: env.contract.address.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+    let prize = Prize::Cw20 { contract: token_addr, amount };
+    let resp = exec_add_prize(deps, env, info.sender, raffle_id, prize)?;
+    Ok(resp.add_message(pull_msg))
+}
+
+/// Prizes can be added by the creator until the raffle opens: before start_time and
+/// before any ticket sells.
+fn exec_add_prize(deps: DepsMut, env: Env, sender: Addr, raffle_id: u64, prize: Prize) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    if sender != raffle.creator { return Err(ContractError::Unauthorized {}); }
+    if !matches!(raffle.status, RaffleStatus::Active) { return Err(ContractError::RaffleNotActive { raffle_id }); }
+    let started = raffle.start_time.map(|st| env.block.time >= st).unwrap_or(false);
+    // Lottery prizes are the pot itself
+    if started || raffle.total_sold > 0 || raffle.pot.is_some() { return Err(ContractError::PrizesLocked { raffle_id }); }
+    // Prizes of a raffle whose NFT was never escrowed would never be paid out or returned
+    if !matches!(raffle.nft_escrow, EscrowStatus::Held) { return Err(ContractError::PrizesNotEscrowed { raffle_id }); }
+    if raffle.prizes.len() >= MAX_PRIZES { return Err(ContractError::TooManyPrizes { max: MAX_PRIZES }); }
+    ensure_prize_funded(&prize)?;
+
+    raffle.prizes.push(prize.clone());
+    RAFFLES.save(deps.storage, raffle_id, &raffle)?;
+    Ok(Response::new()
+        .add_attribute("action", "prize_added")
+        .add_attribute("raffle_id", raffle_id.to_string())
+        .add_attribute("prize_index", (raffle.prizes.len() - 1).to_string())
+        .add_attributes(prize_attributes(&prize)))
+}
+
+fn ensure_prize_funded(prize: &Prize) -> Result<(), ContractError> {
+    let funded = match prize {
+        Prize::Nft { .. } => true,
+        Prize::Coins { amount } => !amount.is_empty() && amount.iter().all(|c| !c.amount.is_zero()),
+        Prize::Cw20 { amount, .. } => !amount.is_zero(),
+    };
+    if !funded { return Err(ContractError::EmptyPrize {}); }
+    Ok(())
+}
+
+/// Event attributes describing a prize, cw721_addr/token_id for NFTs as before
+fn prize_attributes(prize: &Prize) -> Vec<Attribute> {
+    match prize {
+        Prize::Nft { contract, token_id } => vec![
+            Attribute::new("cw721_addr", contract.to_string()),
+            Attribute::new("token_id", token_id.clone()),
+        ],
+        Prize::Coins { amount } => vec![
+            Attribute::new("prize_coins", amount.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")),
+        ],
+        Prize::Cw20 { contract, amount } => vec![
+            Attribute::new("prize_cw20", contract.to_string()),
+            Attribute::new("prize_amount", amount.to_string()),
+        ],
+    }
 }
 
 fn exec_receive_cw20(
@@ -78,61 +265,185 @@ fn exec_receive_cw20(
     env: Env,
     info: MessageInfo,
     msg: cw20::Cw20ReceiveMsg,
-) -> StdResult<Response> {
-    let hook: BuyTicketsCw20Msg = serde_json_wasm::from_slice(&msg.msg).map_err(|_| StdError::generic_err("invalid msg"))?;
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&msg.sender)?;
+    if let Ok(hook) = serde_json_wasm::from_slice::<BuyTicketsCw20Msg>(&msg.msg) {
+        return exec_buy_tickets_cw20(deps, env, info.sender, sender, msg.amount, hook);
+    }
+    // Otherwise the sent tokens are the prize of a new raffle. Like the cw721 hook, the
+    // claimed sender can't authorize adding to an existing raffle (see AddCw20Prize).
+    let prize = Prize::Cw20 { contract: info.sender, amount: msg.amount };
+    let hook: CreateRaffleNftMsg = serde_json_wasm::from_slice(&msg.msg).map_err(|_| ContractError::InvalidHookMsg {})?;
+    let resp = create_raffle(deps, env, sender, vec![prize], None, hook)?;
+    Ok(resp.add_attribute("escrow", "deposit"))
+}
+
+fn exec_buy_tickets_cw20(
+    deps: DepsMut,
+    env: Env,
+    token_addr: Addr,
+    buyer: Addr,
+    amount: Uint128,
+    hook: BuyTicketsCw20Msg,
+) -> Result<Response, ContractError> {
     let raffle = RAFFLES.load(deps.storage, hook.raffle_id)?;
-    let token_addr = info.sender.clone();
-    let expected = raffle.payment_cw20.clone().ok_or_else(|| StdError::generic_err("raffle expects native payment"))?;
-    if expected != token_addr { return Err(StdError::generic_err("wrong payment token")); }
-    let amount = msg.amount.u128();
-    if amount % raffle.price.amount.u128() != 0 { return Err(StdError::generic_err("invalid cw20 amount")); }
-    let count = (amount / raffle.price.amount.u128()) as u64;
-    // Simulate buyer is original sender in hook
-    let buyer = deps.api.addr_validate(&msg.sender)?;
-    exec_buy_tickets_with_count(deps, env, buyer, hook.raffle_id, count)
+    let expected = raffle.payment_cw20.clone().ok_or(ContractError::ExpectsNativePayment {})?;
+    if expected != token_addr { return Err(ContractError::WrongPaymentToken { expected: expected.to_string(), got: token_addr.to_string() }); }
+    // Without a count, buy as many whole tickets as the amount covers. A quotient past
+    // u64::MAX can't fit any raffle and fails the max_tickets check.
+    let count = match hook.count {
+        Some(count) => count,
+        None => u64::try_from((amount / raffle.price.amount).u128()).unwrap_or(u64::MAX),
+    };
+    // Send back whatever the amount overpays
+    let required = raffle.price.amount.checked_mul(Uint128::from(count))?;
+    if amount < required { return Err(ContractError::InsufficientPayment { required, paid: amount }); }
+    let change = amount - required;
+    let mut resp = exec_buy_tickets_with_count(deps, env, buyer.clone(), hook.raffle_id, count, hook.entropy)?;
+    if !change.is_zero() {
+        resp = resp.add_message(payment_msg(&raffle, &buyer, change)?)
+            .add_attribute("refund", change.to_string());
+    }
+    Ok(resp)
 }
 
+/// Direct creation pulls the NFT from the creator, which requires a prior cw721 approval.
 fn exec_create_raffle(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     nft_contract: String,
     token_id: String,
-    price: Coin,
-    max_tickets: u64,
-    start_time: Option<Timestamp>,
-    end_time: Timestamp,
-    revenue_address: Option<String>,
-    payment_cw20: Option<String>,
-) -> StdResult<Response> {
+    params: CreateRaffleNftMsg,
+) -> Result<Response, ContractError> {
+    cw_utils::nonpayable(&info)?;
+    let nft_addr = deps.api.addr_validate(&nft_contract)?;
+    ensure_nft_approved(deps.as_ref(), &env, &info.sender, &nft_addr, &token_id)?;
+
+    let pull_msg = transfer_nft_msg(&nft_addr, &env.contract.address, &token_id)?;
+    let prize = Prize::Nft { contract: nft_addr, token_id };
+    let resp = create_raffle(deps, env, info.sender, vec![prize], None, params)?;
+    Ok(resp.add_message(pull_msg).add_attribute("escrow", "pull"))
+}
+
+/// The sender must own the token and have approved the contract for it or for all their tokens
+fn ensure_nft_approved(deps: Deps, env: &Env, sender: &Addr, nft_addr: &Addr, token_id: &str) -> Result<(), ContractError> {
+    let owner: cw721::OwnerOfResponse = deps.querier.query_wasm_smart(
+        nft_addr,
+        &cw721::Cw721QueryMsg::OwnerOf { token_id: token_id.to_string(), include_expired: Some(false) },
+    )?;
+    if owner.owner != sender.as_str() { return Err(ContractError::NotNftOwner {}); }
+    let token_approved = owner.approvals.iter().any(|a| a.spender == env.contract.address.as_str());
+    if !token_approved {
+        let operator: StdResult<cw721::OperatorResponse> = deps.querier.query_wasm_smart(
+            nft_addr,
+            &cw721::Cw721QueryMsg::Operator {
+                owner: owner.owner.clone(),
+                operator: env.contract.address.to_string(),
+                include_expired: Some(false),
+            },
+        );
+        if operator.is_err() { return Err(ContractError::NftNotApproved {}); }
+    }
+    Ok(())
+}
+
+fn create_raffle(
+    deps: DepsMut,
+    env: Env,
+    creator: Addr,
+    prizes: Vec<Prize>,
+    mut pot: Option<Pot>,
+    params: CreateRaffleNftMsg,
+) -> Result<Response, ContractError> {
+    let CreateRaffleNftMsg { mut price, max_tickets, min_tickets, max_tickets_per_wallet, max_tickets_per_tx, start_time, end_time, revenue_address, payment_cw20, drand_chain, randomness_source } = params;
+    ensure_not_paused(deps.storage)?;
     if end_time <= env.block.time {
-        return Err(StdError::generic_err("end_time must be in the future"));
+        return Err(ContractError::EndTimeNotInFuture {});
     }
-    if let Some(st) = start_time { if st >= end_time { return Err(StdError::generic_err("start_time < end_time required")); } }
+    if let Some(st) = start_time { if st >= end_time { return Err(ContractError::InvalidStartTime {}); } }
+    if let Some(min) = min_tickets { if min > max_tickets { return Err(ContractError::InvalidMinTickets { min_tickets: min, max_tickets }); } }
+    if max_tickets_per_wallet == Some(0) || max_tickets_per_tx == Some(0) { return Err(ContractError::ZeroTicketCap {}); }
 
-    let nft_addr = deps.api.addr_validate(&nft_contract)?;
     let revenue_address = revenue_address
         .map(|s| deps.api.addr_validate(&s))
         .transpose()? // Option<Result> -> Result<Option>
-        .unwrap_or(info.sender.clone());
+        .unwrap_or(creator.clone());
 
+    // Pin the raffle to a beacon chain so later registry changes can't move its draw
+    let randomness_source = randomness_source.unwrap_or_default();
+    let drand_chain = match &randomness_source {
+        RandomnessSource::Drand => {
+            let cfg = CONFIG.load(deps.storage)?;
+            let chain_hash = drand_chain
+                .or(cfg.default_drand_chain)
+                .ok_or(ContractError::NoDrandChainConfigured {})?;
+            load_active_drand_chain(deps.as_ref(), &chain_hash)?.chain_hash
+        }
+        RandomnessSource::CommitReveal { commitment, reveal_timeout } => {
+            if hex::decode(commitment).map(|c| c.len()) != Ok(32) || *commitment != commitment.to_lowercase() {
+                return Err(ContractError::InvalidCommitment {});
+            }
+            if *reveal_timeout == 0 { return Err(ContractError::ZeroRevealTimeout {}); }
+            String::new()
+        }
+    };
+
+    // cw20-priced raffles carry the token address as their price denom
+    let payment_cw20 = payment_cw20.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    if let Some(token) = &payment_cw20 {
+        price.denom = token.to_string();
+    }
+    if price.amount.is_zero() { return Err(ContractError::ZeroTicketPrice {}); }
+    for prize in &prizes {
+        ensure_prize_funded(prize)?;
+    }
+
+    let mut pot_attrs = vec![];
+    if let Some(pot) = pot.as_mut() {
+        let tiers_valid = !pot.tiers_bps.is_empty()
+            && pot.tiers_bps.len() <= MAX_PRIZES
+            && pot.tiers_bps.iter().all(|bps| *bps > 0)
+            && pot.tiers_bps.iter().map(|bps| *bps as u32).sum::<u32>() == 10_000;
+        if !tiers_valid { return Err(ContractError::InvalidPotTiers { max: MAX_PRIZES }); }
+        // A new round picks up whatever earlier rounds of its series left undrawn
+        if let Some(series) = &pot.series {
+            let key = (&creator, series.as_str(), price.denom.as_str());
+            pot.rollover = SERIES_POTS.may_load(deps.storage, key)?.unwrap_or_default();
+            SERIES_POTS.remove(deps.storage, key);
+        }
+        pot_attrs.push(Attribute::new("pot_tiers_bps", pot.tiers_bps.iter().map(|bps| bps.to_string()).collect::<Vec<_>>().join(",")));
+        pot_attrs.push(Attribute::new("series", pot.series.clone().unwrap_or_default()));
+        pot_attrs.push(Attribute::new("rollover", pot.rollover.to_string()));
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
     let next_id = NEXT_ID.load(deps.storage)?;
+    let prize_attrs: Vec<Attribute> = prizes.iter().flat_map(prize_attributes).collect();
 
-    // NOTE: In a full implementation, require prior transfer/approval of the NFT to this contract.
     let raffle = Raffle {
         id: next_id,
-        creator: info.sender.clone(),
-        nft_contract: nft_addr,
-        token_id,
+        creator,
+        prizes,
+        nft_escrow: EscrowStatus::Held,
         price,
         max_tickets,
+        min_tickets,
+        max_tickets_per_wallet,
+        max_tickets_per_tx,
         total_sold: 0,
         start_time,
         end_time,
         revenue_address,
-        payment_cw20: payment_cw20.map(|s| deps.api.addr_validate(&s)).transpose()?.into(),
+        payment_cw20,
+        randomness_source,
+        drand_chain,
+        drand_round: None,
         status: RaffleStatus::Active,
-        winner: None,
+        winners: vec![],
+        pot,
+        protocol_fee_bps: Some(cfg.protocol_fee_bps),
+        bounty: cfg.bounty_amount,
     };
     RAFFLES.save(deps.storage, raffle.id, &raffle)?;
     NEXT_ID.save(deps.storage, &(next_id + 1))?;
@@ -141,274 +452,962 @@ fn exec_create_raffle(
         .add_attribute("action", "raffle_created")
         .add_attribute("raffle_id", raffle.id.to_string())
         .add_attribute("creator", raffle.creator)
-        .add_attribute("cw721_addr", raffle.nft_contract)
-        .add_attribute("token_id", raffle.token_id)
+        .add_attributes(prize_attrs)
+        .add_attributes(pot_attrs)
         .add_attribute("ticket_price", format!("{}{}", raffle.price.amount, raffle.price.denom))
         .add_attribute("start_time", raffle.start_time.map(|t| t.seconds().to_string()).unwrap_or_default())
         .add_attribute("end_time", raffle.end_time.seconds().to_string())
         .add_attribute("max_tickets", raffle.max_tickets.to_string())
+        .add_attribute("min_tickets", raffle.min_tickets.map(|m| m.to_string()).unwrap_or_default())
+        .add_attribute("max_tickets_per_wallet", raffle.max_tickets_per_wallet.map(|m| m.to_string()).unwrap_or_default())
+        .add_attribute("max_tickets_per_tx", raffle.max_tickets_per_tx.map(|m| m.to_string()).unwrap_or_default())
         .add_attribute("revenue_addr", raffle.revenue_address)
         .add_attribute("payment_denom", raffle.price.denom)
+        .add_attribute("drand_chain", raffle.drand_chain)
     )
 }
 
-fn exec_buy_tickets(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64, count: u64) -> StdResult<Response> {
-    if count == 0 { return Err(StdError::generic_err("count must be > 0")); }
-    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
-    if !matches!(raffle.status, RaffleStatus::Active) { return Err(StdError::generic_err("raffle not active")); }
-    if env.block.time < raffle.start_time.unwrap_or(env.block.time) { return Err(StdError::generic_err("raffle not started")); }
-    if env.block.time > raffle.end_time { return Err(StdError::generic_err("raffle ended")); }
-    if raffle.total_sold + count > raffle.max_tickets { return Err(StdError::generic_err("exceeds max tickets")); }
+fn exec_buy_tickets(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64, count: u64, entropy: Option<String>) -> Result<Response, ContractError> {
+    let raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    if raffle.payment_cw20.is_some() { return Err(ContractError::ExpectsCw20Payment {}); }
 
     // Payment check
-    let paid = cw_utils::must_pay(&info, &raffle.price.denom).map_err(|e| StdError::generic_err(e.to_string()))?;
-    let required = Uint128::from(raffle.price.amount.u128() * (count as u128));
-    if paid < required { return Err(StdError::generic_err("insufficient payment")); }
+    let paid = cw_utils::must_pay(&info, &raffle.price.denom)?;
+    let required = raffle.price.amount.checked_mul(Uint128::from(count))?;
+    if paid < required { return Err(ContractError::InsufficientPayment { required, paid }); }
 
-    // Update storage and ticket mapping (index -> owner)
-    for i in 0..count {
-        let idx = raffle.total_sold + i;
-        TICKETS.save(deps.storage, (raffle_id, idx), &info.sender)?;
+    let mut resp = exec_buy_tickets_with_count(deps, env, info.sender.clone(), raffle_id, count, entropy)?;
+    // Refund any overpayment in the same response
+    let excess = paid - required;
+    if !excess.is_zero() {
+        resp = resp.add_message(payment_msg(&raffle, &info.sender, excess)?)
+            .add_attribute("refund", excess.to_string());
     }
-    raffle.total_sold += count;
-    RAFFLES.save(deps.storage, raffle_id, &raffle)?;
-    let current = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &info.sender))?.unwrap_or(0);
-    USER_TICKET_COUNT.save(deps.storage, (raffle_id, &info.sender), &(current + count))?;
-
-    Ok(Response::new()
-        .add_attribute("action", "tickets_bought")
-        .add_attribute("raffle_id", raffle_id.to_string())
-        .add_attribute("buyer", info.sender)
-        .add_attribute("quantity", count.to_string())
-        .add_attribute("total_paid", required.to_string())
-        .add_attribute("denom", raffle.price.denom)
-    )
+    Ok(resp)
 }
 
-fn exec_buy_tickets_with_count(deps: DepsMut, env: Env, buyer: Addr, raffle_id: u64, count: u64) -> StdResult<Response> {
-    if count == 0 { return Err(StdError::generic_err("count must be > 0")); }
+fn exec_buy_tickets_with_count(deps: DepsMut, env: Env, buyer: Addr, raffle_id: u64, count: u64, entropy: Option<String>) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    if count == 0 { return Err(ContractError::ZeroTicketCount {}); }
     let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
-    if !matches!(raffle.status, RaffleStatus::Active) { return Err(StdError::generic_err("raffle not active")); }
-    if env.block.time < raffle.start_time.unwrap_or(env.block.time) { return Err(StdError::generic_err("raffle not started")); }
-    if env.block.time > raffle.end_time { return Err(StdError::generic_err("raffle ended")); }
-    if raffle.total_sold + count > raffle.max_tickets { return Err(StdError::generic_err("exceeds max tickets")); }
-
-    for i in 0..count {
-        let idx = raffle.total_sold + i;
-        TICKETS.save(deps.storage, (raffle_id, idx), &buyer)?;
+    if !matches!(raffle.status, RaffleStatus::Active) { return Err(ContractError::RaffleNotActive { raffle_id }); }
+    if env.block.time < raffle.start_time.unwrap_or(env.block.time) { return Err(ContractError::RaffleNotStarted { raffle_id }); }
+    if env.block.time > raffle.end_time { return Err(ContractError::RaffleEnded { raffle_id }); }
+    // The creator knows the secret, so could pick entropy that makes their own ticket win
+    if matches!(raffle.randomness_source, RandomnessSource::CommitReveal { .. })
+        && (buyer == raffle.creator || buyer == raffle.revenue_address)
+    {
+        return Err(ContractError::CommitterCannotBuy { raffle_id });
+    }
+    if count > raffle.max_tickets.saturating_sub(raffle.total_sold) {
+        return Err(ContractError::ExceedsMaxTickets { requested: count, remaining: raffle.max_tickets.saturating_sub(raffle.total_sold) });
+    }
+    if let Some(max) = raffle.max_tickets_per_tx {
+        if count > max { return Err(ContractError::ExceedsTxCap { requested: count, max }); }
+    }
+    let current = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &buyer))?.unwrap_or(0);
+    if let Some(max) = raffle.max_tickets_per_wallet {
+        if current + count > max {
+            return Err(ContractError::ExceedsWalletCap { requested: count, remaining: max.saturating_sub(current) });
+        }
     }
+
+    // One range entry per purchase: tickets total_sold..total_sold + count
+    let first_ticket = raffle.total_sold;
+    TICKET_RANGES.save(deps.storage, (raffle_id, first_ticket), &TicketRange { owner: buyer.clone(), count })?;
+    OWNER_TICKET_RANGES.save(deps.storage, (raffle_id, &buyer, first_ticket), &count)?;
     raffle.total_sold += count;
+    let total_paid = raffle.price.amount.checked_mul(Uint128::from(count))?;
+    pin_round_if_sold_out(deps.as_ref(), &env, &mut raffle)?;
+    if let RandomnessSource::CommitReveal { .. } = raffle.randomness_source {
+        mix_entropy(deps.storage, &env, raffle_id, &buyer, count, entropy.as_deref())?;
+    }
     RAFFLES.save(deps.storage, raffle_id, &raffle)?;
-    let current = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &buyer))?.unwrap_or(0);
     USER_TICKET_COUNT.save(deps.storage, (raffle_id, &buyer), &(current + count))?;
+    USER_RAFFLES.save(deps.storage, (&buyer, raffle_id), &true)?;
 
     Ok(Response::new()
         .add_attribute("action", "tickets_bought")
         .add_attribute("raffle_id", raffle_id.to_string())
         .add_attribute("buyer", buyer)
         .add_attribute("quantity", count.to_string())
-        .add_attribute("total_paid", Uint128::from(raffle.price.amount.u128() * (count as u128)).to_string())
+        .add_attribute("first_ticket", first_ticket.to_string())
+        .add_attribute("last_ticket", (first_ticket + count - 1).to_string())
+        .add_attribute("total_paid", total_paid.to_string())
         .add_attribute("denom", raffle.price.denom))
 }
 
-fn exec_end_raffle(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64, drand_round: u64, randomness: String, signature: String) -> StdResult<Response> {
-    let cfg = CONFIG.load(deps.storage)?;
+/// Owner of ticket `index`: the range with the greatest start <= index, a single
+/// descending seek over the ordered (raffle_id, start) keys.
+fn ticket_owner(storage: &dyn Storage, raffle_id: u64, index: u64) -> StdResult<Option<Addr>> {
+    let range = TICKET_RANGES
+        .prefix(raffle_id)
+        .range(storage, None, Some(Bound::inclusive(index)), cosmwasm_std::Order::Descending)
+        .next()
+        .transpose()?;
+    Ok(range.and_then(|(start, r)| (index < start + r.count).then_some(r.owner)))
+}
+
+/// Fold a purchase into a commit-reveal raffle's entropy:
+/// sha256(previous || buyer || count || height || time || entropy).
+fn mix_entropy(storage: &mut dyn Storage, env: &Env, raffle_id: u64, buyer: &Addr, count: u64, entropy: Option<&str>) -> StdResult<()> {
+    let previous = ENTROPY.may_load(storage, raffle_id)?.unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(previous.as_slice());
+    hasher.update(buyer.as_bytes());
+    hasher.update(count.to_be_bytes());
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    hasher.update(entropy.unwrap_or_default().as_bytes());
+    ENTROPY.save(storage, raffle_id, &Binary::from(hasher.finalize().to_vec()))
+}
+
+/// Sold-out raffles close now, so fix the first round published after this block.
+fn pin_round_if_sold_out(deps: Deps, env: &Env, raffle: &mut Raffle) -> Result<(), ContractError> {
+    let drand = matches!(raffle.randomness_source, RandomnessSource::Drand);
+    if drand && raffle.total_sold >= raffle.max_tickets && raffle.drand_round.is_none() {
+        let chain = DRAND_CHAINS.load(deps.storage, &raffle.drand_chain)?;
+        raffle.drand_round = Some(drand_round_after(&chain, env.block.time));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn exec_end_raffle(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64, drand_round: u64, randomness: String, signature: String, previous_signature: Option<String>) -> Result<Response, ContractError> {
     let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
-    if !matches!(raffle.status, RaffleStatus::Active) { return Err(StdError::generic_err("raffle not active")); }
-    let time_end = env.block.time >= raffle.end_time;
-    let sold_out = raffle.total_sold >= raffle.max_tickets;
-    if !(time_end || sold_out) { return Err(StdError::generic_err("raffle not ready to end")); }
-    if raffle.total_sold == 0 { return Err(StdError::generic_err("no tickets sold")); }
+    // Failing takes no beacon, so it comes before any drand check
+    if missed_min_tickets(&raffle, env.block.time) { return fail_raffle(deps.storage, &mut raffle); }
+    if !matches!(raffle.randomness_source, RandomnessSource::Drand) { return Err(ContractError::WrongRandomnessSource { raffle_id }); }
+    let sold_out = ensure_drawable(deps.storage, &raffle, &env)?;
 
-    // Drand round minimum based on end time, if configured
-    if let Some(round_secs) = cfg.drand_round_seconds {
-        let min_round = raffle.end_time.seconds() / round_secs;
-        if drand_round <= min_round { return Err(StdError::generic_err("drand_round too old")); }
+    // Only the round fixed at close is accepted, so the caller can't pick among rounds
+    let chain = DRAND_CHAINS.load(deps.storage, &raffle.drand_chain)?;
+    let target_round = raffle.drand_round.unwrap_or_else(|| drand_round_after(&chain, raffle.end_time));
+    if drand_round != target_round {
+        return Err(ContractError::DrandRoundMismatch { expected: target_round, got: drand_round });
     }
+
     if USED_ROUNDS.may_load(deps.storage, (raffle_id, drand_round))?.unwrap_or(false) {
-        return Err(StdError::generic_err("drand round already used"));
+        return Err(ContractError::DrandRoundUsed { round: drand_round });
     }
 
-    // Verify drand BLS signature
-    if cfg.drand_pubkey.is_none() || randomness.is_empty() || signature.is_empty() {
-        return Err(StdError::generic_err("invalid drand input"));
+    if randomness.is_empty() || signature.is_empty() {
+        return Err(ContractError::InvalidDrandInput {});
     }
-    
-    // Verify BLS signature against drand public key
+
+    // Verify BLS signature against the raffle's pinned drand chain
     let is_valid = verify_drand_signature(
-        &env,
-        &cfg.drand_pubkey.as_ref().unwrap(),
+        &chain,
         drand_round,
         &randomness,
-        &signature
+        &signature,
+        previous_signature.as_deref(),
     )?;
     
     if !is_valid {
-        return Err(StdError::generic_err("invalid drand signature"));
+        return Err(ContractError::InvalidDrandSignature {});
     }
 
-    // Derive winner index from randomness
-    // Use first 8 bytes of randomness hex
-    let rnd_bytes = hex::decode(randomness).map_err(|_| StdError::generic_err("bad randomness hex"))?;
-    if rnd_bytes.is_empty() { return Err(StdError::generic_err("empty randomness")); }
-    // no-op
-    let slice = if rnd_bytes.len() >= 8 { &rnd_bytes[..8] } else { &rnd_bytes[..] };
-    let mut u64buf = [0u8; 8];
-    for (i, b) in slice.iter().enumerate() { u64buf[i] = *b; }
-    let seed = u64::from_be_bytes(u64buf);
-    let winner_index = (seed % raffle.total_sold) as u64; // 0..total_sold-1
-
-    // lookup owner at winner_index
-    let winner = TICKETS.load(deps.storage, (raffle_id, winner_index))?;
-
-    raffle.status = RaffleStatus::Completed;
-    raffle.winner = Some(winner.clone());
-    RAFFLES.save(deps.storage, raffle_id, &raffle)?;
+    let rnd_bytes = hex::decode(&randomness).map_err(|_| ContractError::InvalidHex { what: "randomness".into() })?;
+    raffle.drand_round = Some(drand_round);
     USED_ROUNDS.save(deps.storage, (raffle_id, drand_round), &true)?;
+    let proof = DrawProof::Drand { chain: chain.chain_hash, round: drand_round, signature, previous_signature };
+    let resp = settle_draw(deps, &env, &info.sender, raffle, &rnd_bytes, randomness, proof)?;
+    Ok(resp
+        .add_attribute("end_reason", if sold_out { "soldout" } else { "time" })
+        .add_attribute("drand_round", drand_round.to_string()))
+}
 
-    // Transfer NFT to winner
-    let transfer_msg = WasmMsg::Execute {
-        contract_addr: raffle.nft_contract.to_string(),
-        msg: to_json_binary(&serde_json::json!({
-            "transfer_nft": {
-                "recipient": winner.to_string(),
-                "token_id": raffle.token_id,
-            }
-        }))?,
-        funds: vec![],
+/// Settles a commit-reveal raffle: the creator reveals the secret committed at creation
+/// and the draw uses sha256(secret || buyer entropy).
+fn exec_reveal_randomness(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64, secret: String) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    let RandomnessSource::CommitReveal { commitment, .. } = raffle.randomness_source.clone() else {
+        return Err(ContractError::WrongRandomnessSource { raffle_id });
     };
+    if info.sender != raffle.creator { return Err(ContractError::Unauthorized {}); }
+    if missed_min_tickets(&raffle, env.block.time) { return fail_raffle(deps.storage, &mut raffle); }
+    let sold_out = ensure_drawable(deps.storage, &raffle, &env)?;
+    if reveal_expired(&raffle, env.block.time) { return Err(ContractError::RevealDeadlinePassed { raffle_id }); }
+
+    let secret_bytes = hex::decode(&secret).map_err(|_| ContractError::InvalidHex { what: "secret".into() })?;
+    if hex::encode(Sha256::digest(&secret_bytes)) != commitment {
+        return Err(ContractError::RevealMismatch {});
+    }
+    let entropy = ENTROPY.may_load(deps.storage, raffle_id)?.unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(&secret_bytes);
+    hasher.update(entropy.as_slice());
+    let rnd_bytes: [u8; 32] = hasher.finalize().into();
+
+    let proof = DrawProof::CommitReveal { secret, entropy: hex::encode(entropy.as_slice()) };
+    let resp = settle_draw(deps, &env, &info.sender, raffle, &rnd_bytes, hex::encode(rnd_bytes), proof)?;
+    Ok(resp
+        .add_attribute("end_reason", if sold_out { "soldout" } else { "time" })
+        .add_attribute("randomness_source", "commit_reveal"))
+}
+
+/// Closed raffles with tickets sold can be drawn; returns whether it sold out
+fn ensure_drawable(storage: &dyn Storage, raffle: &Raffle, env: &Env) -> Result<bool, ContractError> {
+    if !matches!(raffle.status, RaffleStatus::Active) { return Err(ContractError::RaffleNotActive { raffle_id: raffle.id }); }
+    let time_end = env.block.time >= raffle.end_time;
+    let sold_out = raffle.total_sold >= raffle.max_tickets;
+    if !(time_end || sold_out) { return Err(ContractError::RaffleNotReadyToEnd { raffle_id: raffle.id }); }
+    if raffle.total_sold == 0 { return Err(ContractError::NoTicketsSold { raffle_id: raffle.id }); }
+    // Winners are looked up in TICKET_RANGES, which pre-0.3 tickets reach through Reindex
+    if migrations::tickets_pending(storage, raffle.id)? { return Err(ContractError::TicketsNotMigrated { raffle_id: raffle.id }); }
+    Ok(sold_out)
+}
+
+fn below_min_tickets(raffle: &Raffle) -> bool {
+    raffle.total_sold < raffle.min_tickets.unwrap_or(0)
+}
+
+/// Closed with some sales but fewer than min_tickets, so the raffle fails instead of
+/// drawing. EndRaffle, RevealRandomness and ExpireRaffle all take this path.
+fn missed_min_tickets(raffle: &Raffle, now: Timestamp) -> bool {
+    matches!(raffle.status, RaffleStatus::Active) && now >= raffle.end_time && raffle.total_sold > 0 && below_min_tickets(raffle)
+}
+
+/// No draw: return the prizes and open refunds
+fn fail_raffle(storage: &mut dyn Storage, raffle: &mut Raffle) -> Result<Response, ContractError> {
+    raffle.status = RaffleStatus::Failed;
+    let resp = return_prizes_to_creator(storage, raffle)?;
+    RAFFLES.save(storage, raffle.id, raffle)?;
+    Ok(resp
+        .add_attribute("action", "raffle_failed")
+        .add_attribute("raffle_id", raffle.id.to_string())
+        .add_attribute("total_sold", raffle.total_sold.to_string())
+        .add_attribute("min_tickets", raffle.min_tickets.unwrap_or(0).to_string()))
+}
+
+/// Commit-reveal raffles whose creator let the reveal window pass are refundable
+fn reveal_expired(raffle: &Raffle, now: Timestamp) -> bool {
+    match raffle.randomness_source {
+        RandomnessSource::CommitReveal { reveal_timeout, .. } => {
+            matches!(raffle.status, RaffleStatus::Active) && now > raffle.end_time.plus_seconds(reveal_timeout)
+        }
+        RandomnessSource::Drand => false,
+    }
+}
+
+/// Pay out a raffle from verified randomness: fee, bounty to `executor`, prizes to the
+/// drawn winners and the remainder to revenue_address.
+fn settle_draw(deps: DepsMut, env: &Env, executor: &Addr, mut raffle: Raffle, rnd_bytes: &[u8], randomness: String, proof: DrawProof) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let raffle_id = raffle.id;
 
     // Distribute funds: protocol fee, bounty to executor, remainder to revenue_address
-    let denom = raffle.price.denom.clone();
-    let total = Uint128::from(raffle.price.amount.u128() * (raffle.total_sold as u128));
+    let denom = payment_denom(&raffle);
+    let total = raffle.price.amount.checked_mul(Uint128::from(raffle.total_sold))?;
     let mut remaining = total;
 
     let mut resp = Response::new()
-        .add_message(transfer_msg)
         .add_attribute("action", "raffle_ended")
         .add_attribute("raffle_id", raffle_id.to_string())
-        .add_attribute("end_reason", if sold_out { "soldout" } else { "time" })
-        .add_attribute("drand_round", drand_round.to_string());
+        .add_attribute("denom", denom.clone());
 
-    // protocol fee
-    if cfg.protocol_fee_bps > 0 {
-        let fee = total.multiply_ratio(cfg.protocol_fee_bps as u128, 10_000u128);
+    // protocol fee fixed at creation, retained by the contract until WithdrawFees. Once
+    // the admin is renounced nobody could withdraw it, so none is taken.
+    let fee_bps = match cfg.admin {
+        Some(_) => raffle.protocol_fee_bps.unwrap_or(cfg.protocol_fee_bps),
+        None => 0,
+    };
+    if fee_bps > 0 {
+        let fee = total.multiply_ratio(fee_bps as u128, 10_000u128);
         if !fee.is_zero() {
-            remaining = remaining.checked_sub(fee).map_err(|_| StdError::generic_err("fee exceeds total"))?;
-            resp = resp.add_message(BankMsg::Send { to_address: cfg.admin.to_string(), amount: vec![Coin { denom: denom.clone(), amount: fee }]})
-                .add_attribute("protocol_fee", fee.to_string());
+            remaining = remaining.checked_sub(fee)?;
+            add_collected_fee(deps.storage, &denom, raffle.payment_cw20.is_some(), fee)?;
+            resp = resp.add_attribute("protocol_fee", fee.to_string());
         }
     }
 
-    // bounty to executor
-    if let Some(b) = cfg.bounty_amount.clone() {
+    // bounty to executor, paid when its denom (native, or cw20 address) matches the raffle's
+    // and capped at MAX_BOUNTY_BPS of the sales
+    if let Some(b) = raffle_bounty(&raffle, &cfg) {
         if b.denom == denom && !b.amount.is_zero() {
-            let pay = if remaining >= b.amount { b.amount } else { remaining };
+            let pay = b.amount.min(total.multiply_ratio(MAX_BOUNTY_BPS, 10_000u128)).min(remaining);
             if !pay.is_zero() {
-                remaining = remaining.checked_sub(pay).map_err(|_| StdError::generic_err("bounty exceeds remaining"))?;
-                resp = resp.add_message(BankMsg::Send { to_address: info.sender.to_string(), amount: vec![Coin { denom: denom.clone(), amount: pay }]})
+                remaining = remaining.checked_sub(pay)?;
+                resp = resp.add_message(payment_msg(&raffle, executor, pay)?)
                     .add_attribute("bounty_paid", pay.to_string());
             }
         }
     }
 
+    // Lotteries turn the rest of the sales plus any rollover into their prizes
+    if let Some(pot) = raffle.pot.clone() {
+        let pot_total = remaining.checked_add(pot.rollover)?;
+        raffle.prizes = pot_prizes(&raffle, &pot.tiers_bps, pot_total);
+        resp = resp.add_attribute("pot", pot_total.to_string());
+        remaining = Uint128::zero();
+    }
+
+    let winners = draw_winners(deps.storage, &env.contract.address, &raffle, rnd_bytes)?;
+
+    // Each prize goes to its winner. Prizes left over once every buyer has won go back
+    // to the creator, or for lotteries roll into the series' next round.
+    let mut unawarded = Uint128::zero();
+    let escrowed = matches!(raffle.nft_escrow, EscrowStatus::Held);
+    for (i, prize) in raffle.prizes.iter().enumerate() {
+        if ensure_prize_funded(prize).is_err() || !escrowed { continue; }
+        match winners.iter().find(|w| w.prize as usize == i) {
+            Some(w) => resp = resp.add_submessage(prize_transfer_msg(prize, &w.winner)?),
+            None if raffle.pot.is_some() => unawarded += fungible_amount(prize),
+            None => resp = resp.add_submessage(prize_transfer_msg(prize, &raffle.creator)?),
+        }
+    }
+    if !unawarded.is_zero() {
+        if roll_over(deps.storage, &raffle, unawarded)? {
+            resp = resp.add_attribute("rollover", unawarded.to_string());
+        } else {
+            remaining += unawarded;
+        }
+    }
+
+    raffle.status = RaffleStatus::Completed;
+    raffle.winners = winners.clone();
+    if escrowed { raffle.nft_escrow = EscrowStatus::Released; }
+    RAFFLES.save(deps.storage, raffle_id, &raffle)?;
+    for w in &winners {
+        USER_WINS.save(deps.storage, (&w.winner, raffle_id), &w.prize)?;
+    }
+    DRAWS.save(deps.storage, raffle_id, &DrawRecord {
+        raffle_id,
+        proof,
+        randomness,
+        total_sold: raffle.total_sold,
+        winners: winners.clone(),
+        drawn_at: env.block.time,
+    })?;
+
     // payout to revenue address (creator or designated)
     if !remaining.is_zero() {
-        resp = resp.add_message(BankMsg::Send { to_address: raffle.revenue_address.to_string(), amount: vec![Coin { denom: denom.clone(), amount: remaining }]})
+        resp = resp.add_message(payment_msg(&raffle, &raffle.revenue_address, remaining)?)
             .add_attribute("payout", remaining.to_string());
     }
-    resp = resp.add_attribute("action", "winner_selected")
-        .add_attribute("winner", winner)
-        .add_attribute("ticket_index", winner_index.to_string());
+    resp = resp.add_attribute("action", "winner_selected");
+    if let Some(first) = winners.first() {
+        resp = resp.add_attribute("winner", first.winner.to_string())
+            .add_attribute("ticket_index", first.ticket_index.unwrap_or_default().to_string());
+    }
+    for w in &winners {
+        resp = resp.add_attribute("prize_winner", format!("{}:{}:{}", w.prize, w.winner, w.ticket_index.unwrap_or_default()));
+    }
     Ok(resp)
 }
 
-fn exec_cancel_raffle(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64) -> StdResult<Response> {
+/// Split a lottery pot into one payment-asset prize per tier, rounding dust into the first
+fn pot_prizes(raffle: &Raffle, tiers_bps: &[u16], pot: Uint128) -> Vec<Prize> {
+    let mut shares: Vec<Uint128> = tiers_bps.iter().map(|bps| pot.multiply_ratio(*bps as u128, 10_000u128)).collect();
+    let paid: Uint128 = shares.iter().sum();
+    if let Some(first) = shares.first_mut() {
+        *first += pot - paid;
+    }
+    shares
+        .into_iter()
+        .map(|amount| match &raffle.payment_cw20 {
+            Some(token) => Prize::Cw20 { contract: token.clone(), amount },
+            None => Prize::Coins { amount: vec![Coin { denom: raffle.price.denom.clone(), amount }] },
+        })
+        .collect()
+}
+
+/// Amount of a pot prize, which always pays out in the raffle's payment asset
+fn fungible_amount(prize: &Prize) -> Uint128 {
+    match prize {
+        Prize::Nft { .. } => Uint128::zero(),
+        Prize::Coins { amount } => amount.iter().map(|c| c.amount).sum(),
+        Prize::Cw20 { amount, .. } => *amount,
+    }
+}
+
+/// Carry `amount` of a lottery's undrawn pot into its series, for the next round to
+/// pick up. Returns false when the raffle isn't part of a series.
+fn roll_over(storage: &mut dyn Storage, raffle: &Raffle, amount: Uint128) -> Result<bool, ContractError> {
+    let Some(series) = raffle.pot.as_ref().and_then(|p| p.series.as_deref()) else { return Ok(false) };
+    if amount.is_zero() { return Ok(true); }
+    SERIES_POTS.update(storage, (&raffle.creator, series, &payment_denom(raffle)), |pot| -> Result<_, ContractError> {
+        Ok(pot.unwrap_or_default().checked_add(amount)?)
+    })?;
+    Ok(true)
+}
+
+/// Draw a distinct winner for each prize, in prize order, from one drand output, using
+/// winning_ticket(randomness, contract, raffle_id, prize, redraw, total_sold). A ticket
+/// whose owner already won is redrawn with the next `redraw` up to MAX_REDRAWS times,
+/// then the next ticket range held by a new owner is taken. Once every buyer has won,
+/// the remaining prizes go unawarded.
+fn draw_winners(storage: &dyn Storage, contract: &Addr, raffle: &Raffle, randomness: &[u8]) -> Result<Vec<PrizeWinner>, ContractError> {
+    if randomness.is_empty() { return Err(ContractError::InvalidDrandInput {}); }
+    let mut winners: Vec<PrizeWinner> = vec![];
+    for prize in 0..raffle.prizes.len() as u32 {
+        let mut picked = None;
+        let mut index = 0;
+        for redraw in 0..=MAX_REDRAWS {
+            index = winning_ticket(randomness, contract.as_str(), raffle.id, prize, redraw, raffle.total_sold)
+                .ok_or(ContractError::NoTicketsSold { raffle_id: raffle.id })?;
+            let owner = ticket_owner(storage, raffle.id, index)?
+                .ok_or_else(|| StdError::not_found("ticket"))?;
+            if !winners.iter().any(|w| w.winner == owner) {
+                picked = Some((index, owner));
+                break;
+            }
+        }
+        if picked.is_none() {
+            picked = next_new_owner(storage, raffle.id, index, &winners)?;
+        }
+        match picked {
+            Some((index, owner)) => winners.push(PrizeWinner { prize, winner: owner, ticket_index: Some(index) }),
+            None => break,
+        }
+    }
+    Ok(winners)
+}
+
+/// Ticket index that wins prize `prize_index` of raffle `raffle_id`, uniform over
+/// 0..total_sold, or None when no tickets were sold.
+///
+/// For counter = 0, 1, 2, ... hash
+///
+/// ```text
+/// h = sha256("coreum-raffle/winner/v1" || u32_be(len(contract)) || contract
+///            || u64_be(raffle_id) || u32_be(prize_index) || u32_be(redraw)
+///            || u32_be(counter) || randomness)
+/// ```
+///
+/// where `contract` is the raffle contract's bech32 address as UTF-8 bytes and
+/// `randomness` the 32 drand randomness bytes. Read h as a big-endian 256-bit integer x
+/// and return x mod total_sold for the first x below 2^256 - (2^256 mod total_sold).
+/// Rejecting the top partial interval removes modulo bias; for total_sold < 2^64 a
+/// rejection has probability below 2^-192. The contract, raffle and prize fields keep
+/// raffles settled on the same drand round independent of each other. `redraw` is 0
+/// unless earlier draws for this prize hit a ticket whose owner had already won.
+///
+/// A raffle with several prizes draws them in prize order and no address wins twice.
+/// For prize p:
+///
+/// 1. For redraw = 0, 1, ..., MAX_REDRAWS (16) take
+///    `winning_ticket(.., p, redraw, total_sold)`; the first ticket whose owner hasn't
+///    won an earlier prize wins p.
+/// 2. If all MAX_REDRAWS + 1 draws hit earlier winners, let t be the last ticket drawn
+///    and walk the raffle's purchases (one per `tickets_bought` event) by ascending
+///    `first_ticket`: first those with `first_ticket` > t, then wrapping around to
+///    those with `first_ticket` <= t. The first purchase whose buyer hasn't won yet
+///    wins p, with ticket index `first_ticket`.
+/// 3. If every buyer has already won, p and all later prizes go unawarded.
+///
+/// The outcome is emitted as one `prize_winner` attribute per awarded prize, formatted
+/// `<prize>:<winner>:<ticket_index>`, and returned by the `VerifyDraw` query.
+pub fn winning_ticket(randomness: &[u8], contract: &str, raffle_id: u64, prize_index: u32, redraw: u32, total_sold: u64) -> Option<u64> {
+    if total_sold == 0 { return None; }
+    let n = Uint256::from(total_sold);
+    // 2^256 mod n, computed without leaving 256 bits
+    let rem = (Uint256::MAX % n + Uint256::one()) % n;
+    let limit = Uint256::MAX - rem;
+    let mut counter: u32 = 0;
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(WINNER_DOMAIN);
+        hasher.update((contract.len() as u32).to_be_bytes());
+        hasher.update(contract.as_bytes());
+        hasher.update(raffle_id.to_be_bytes());
+        hasher.update(prize_index.to_be_bytes());
+        hasher.update(redraw.to_be_bytes());
+        hasher.update(counter.to_be_bytes());
+        hasher.update(randomness);
+        let x = Uint256::from_be_bytes(hasher.finalize().into());
+        if x <= limit {
+            let index = (x % n).to_be_bytes();
+            let mut low = [0u8; 8];
+            low.copy_from_slice(&index[24..]);
+            return Some(u64::from_be_bytes(low));
+        }
+        counter = counter.wrapping_add(1);
+    }
+}
+
+/// First ticket range after `index`, wrapping around, whose owner hasn't won yet
+fn next_new_owner(storage: &dyn Storage, raffle_id: u64, index: u64, winners: &[PrizeWinner]) -> StdResult<Option<(u64, Addr)>> {
+    let ranges = TICKET_RANGES.prefix(raffle_id);
+    let after = ranges.range(storage, Some(Bound::exclusive(index)), None, cosmwasm_std::Order::Ascending);
+    let before = ranges.range(storage, None, Some(Bound::inclusive(index)), cosmwasm_std::Order::Ascending);
+    for item in after.chain(before) {
+        let (start, r) = item?;
+        if !winners.iter().any(|w| w.winner == r.owner) {
+            return Ok(Some((start, r.owner)));
+        }
+    }
+    Ok(None)
+}
+
+
+pub(crate) fn transfer_nft_msg(nft_contract: &Addr, recipient: &Addr, token_id: &str) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: nft_contract.to_string(),
+        msg: to_json_binary(&serde_json::json!({
+            "transfer_nft": {
+                "recipient": recipient.to_string(),
+                "token_id": token_id,
+            }
+        }))?,
+        funds: vec![],
+    })
+}
+
+/// A prize's contract may refuse the transfer, so its failure is caught in `reply`
+/// instead of reverting the draw, cancel or expiry that pays it out
+/// The bounty pinned at creation, or the config's for raffles from before 0.6.0
+fn raffle_bounty(raffle: &Raffle, cfg: &Config) -> Option<Coin> {
+    match raffle.protocol_fee_bps {
+        Some(_) => raffle.bounty.clone(),
+        None => cfg.bounty_amount.clone(),
+    }
+}
+
+fn prize_transfer_msg(prize: &Prize, recipient: &Addr) -> StdResult<SubMsg> {
+    let msg: CosmosMsg = match prize {
+        Prize::Nft { contract, token_id } => transfer_nft_msg(contract, recipient, token_id)?.into(),
+        Prize::Coins { amount } => BankMsg::Send { to_address: recipient.to_string(), amount: amount.clone() }.into(),
+        Prize::Cw20 { contract, amount } => send_msg(contract.as_str(), true, recipient, *amount)?,
+    };
+    Ok(SubMsg::reply_on_error(msg, PRIZE_TRANSFER_REPLY_ID))
+}
+
+/// Send the escrowed prizes back to the creator, if the contract still holds them,
+/// and a lottery's carried-over pot back to its series
+fn return_prizes_to_creator(storage: &mut dyn Storage, raffle: &mut Raffle) -> Result<Response, ContractError> {
+    let mut resp = Response::new();
+    if matches!(raffle.nft_escrow, EscrowStatus::Held) {
+        for prize in &raffle.prizes {
+            resp = resp.add_submessage(prize_transfer_msg(prize, &raffle.creator)?);
+        }
+        resp = resp.add_attribute("nft_returned", raffle.creator.to_string());
+        let rollover = raffle.pot.as_ref().map(|p| p.rollover).unwrap_or_default();
+        if !rollover.is_zero() && roll_over(storage, raffle, rollover)? {
+            resp = resp.add_attribute("rollover", rollover.to_string());
+        }
+        raffle.nft_escrow = EscrowStatus::Released;
+    }
+    Ok(resp)
+}
+
+fn exec_cancel_raffle(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64) -> Result<Response, ContractError> {
     let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
-    if info.sender != raffle.creator { return Err(StdError::generic_err("unauthorized")); }
-    if raffle.total_sold >= raffle.max_tickets { return Err(StdError::generic_err("cannot cancel after sold out")); }
-    if let Some(st) = raffle.start_time { if env.block.time >= st { return Err(StdError::generic_err("cannot cancel after start")); } }
+    if info.sender != raffle.creator { return Err(ContractError::Unauthorized {}); }
+    if !matches!(raffle.status, RaffleStatus::Active) { return Err(ContractError::RaffleNotActive { raffle_id }); }
+    // Once tickets sell or the raffle closes the draw may already be knowable, so a
+    // cancel would let the creator take back prizes from an outcome they dislike
+    if raffle.total_sold > 0 { return Err(ContractError::TicketsSold { raffle_id }); }
+    if env.block.time >= raffle.end_time { return Err(ContractError::RaffleEnded { raffle_id }); }
+    if let Some(st) = raffle.start_time { if env.block.time >= st { return Err(ContractError::CancelAfterStart {}); } }
     raffle.status = RaffleStatus::Cancelled;
+    // Return the escrowed prizes, buyers pull their refunds with ClaimRefund
+    let resp = return_prizes_to_creator(deps.storage, &mut raffle)?;
+    RAFFLES.save(deps.storage, raffle_id, &raffle)?;
+    Ok(resp.add_attribute("action", "raffle_cancelled").add_attribute("raffle_id", raffle_id.to_string()).add_attribute("creator", info.sender))
+}
+
+fn exec_expire_raffle(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    if !matches!(raffle.status, RaffleStatus::Active) { return Err(ContractError::RaffleNotActive { raffle_id }); }
+    if env.block.time < raffle.end_time { return Err(ContractError::RaffleNotReadyToEnd { raffle_id }); }
+    // Sales that missed min_tickets are never drawn: fail the raffle and open refunds
+    if missed_min_tickets(&raffle, env.block.time) {
+        let resp = fail_raffle(deps.storage, &mut raffle)?;
+        return Ok(resp.add_attribute("caller", info.sender));
+    }
+    if raffle.total_sold > 0 { return Err(ContractError::TicketsSold { raffle_id }); }
+
+    raffle.status = RaffleStatus::Expired;
+    let resp = return_prizes_to_creator(deps.storage, &mut raffle)?;
     RAFFLES.save(deps.storage, raffle_id, &raffle)?;
-    Ok(Response::new().add_attribute("action", "raffle_cancelled").add_attribute("raffle_id", raffle_id.to_string()).add_attribute("creator", info.sender))
+    Ok(resp
+        .add_attribute("action", "raffle_expired")
+        .add_attribute("raffle_id", raffle_id.to_string())
+        .add_attribute("creator", raffle.creator)
+        .add_attribute("caller", info.sender))
+}
+
+fn exec_claim_refund(deps: DepsMut, env: Env, info: MessageInfo, raffle_id: u64) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    // The first claim after a missed reveal fails the raffle and returns its prizes
+    let mut resp = Response::new();
+    if reveal_expired(&raffle, env.block.time) {
+        resp = fail_raffle(deps.storage, &mut raffle)?;
+    }
+    if !refunds_open(&raffle) { return Err(ContractError::NotRefundable { raffle_id }); }
+    if REFUNDS_CLAIMED.has(deps.storage, (raffle_id, &info.sender)) { return Err(ContractError::RefundAlreadyClaimed {}); }
+    let count = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &info.sender))?.unwrap_or(0);
+    if count == 0 { return Err(ContractError::NoTicketsToRefund {}); }
+
+    let amount = raffle.price.amount.checked_mul(Uint128::from(count))?;
+    REFUNDS_CLAIMED.save(deps.storage, (raffle_id, &info.sender), &true)?;
+
+    Ok(resp
+        .add_message(payment_msg(&raffle, &info.sender, amount)?)
+        .add_attribute("action", "refund_claimed")
+        .add_attribute("raffle_id", raffle_id.to_string())
+        .add_attribute("buyer", info.sender)
+        .add_attribute("quantity", count.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("denom", payment_denom(&raffle)))
+}
+
+fn refunds_open(raffle: &Raffle) -> bool {
+    matches!(raffle.status, RaffleStatus::Cancelled | RaffleStatus::Failed)
+}
+
+/// Native denom, or the cw20 contract address for cw20-priced raffles
+fn payment_denom(raffle: &Raffle) -> String {
+    match &raffle.payment_cw20 {
+        Some(token) => token.to_string(),
+        None => raffle.price.denom.clone(),
+    }
+}
+
+/// Send `amount` of the raffle's payment asset, native or cw20, to `to`
+fn payment_msg(raffle: &Raffle, to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    send_msg(&payment_denom(raffle), raffle.payment_cw20.is_some(), to, amount)
+}
+
+/// Send `amount` of a native denom, or of the cw20 token at address `denom`, to `to`
+fn send_msg(denom: &str, cw20: bool, to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(if cw20 {
+        WasmMsg::Execute {
+            contract_addr: denom.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient: to.to_string(), amount })?,
+            funds: vec![],
+        }.into()
+    } else {
+        BankMsg::Send { to_address: to.to_string(), amount: vec![Coin { denom: denom.to_string(), amount }] }.into()
+    })
+}
+
+fn add_collected_fee(storage: &mut dyn Storage, denom: &str, cw20: bool, amount: Uint128) -> StdResult<()> {
+    COLLECTED_FEES.update(storage, denom, |bal| -> StdResult<_> {
+        let mut bal = bal.unwrap_or(FeeBalance { cw20, amount: Uint128::zero() });
+        bal.amount = bal.amount.checked_add(amount)?;
+        Ok(bal)
+    })?;
+    Ok(())
+}
+
+#[entry_point]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        // The failed transfer's state is already reverted: the prize stays with the
+        // contract and everything else in the settlement goes through
+        PRIZE_TRANSFER_REPLY_ID => Ok(Response::new()
+            .add_attribute("action", "prize_transfer_failed")
+            .add_attribute("error", msg.result.unwrap_err())),
+        id => Err(StdError::generic_err(format!("unknown reply id {id}")).into()),
+    }
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<cosmwasm_std::Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<cosmwasm_std::Binary> {
     match msg {
         QueryMsg::Raffle { raffle_id } => to_json_binary(&query_raffle(deps, raffle_id)?),
-        QueryMsg::Raffles { start_after, limit } => to_json_binary(&query_raffles(deps, start_after, limit)?),
+        QueryMsg::Raffles { start_after, limit, status } => to_json_binary(&query_raffles(deps, start_after, limit, status)?),
+        QueryMsg::RafflesByCreator { creator, start_after, limit } => to_json_binary(&query_raffles_by_creator(deps, creator, start_after, limit)?),
+        QueryMsg::RafflesByStatus { status, start_after, limit } => to_json_binary(&query_raffles_by_status(deps, status, start_after, limit)?),
+        QueryMsg::RafflesByCollection { nft_contract, start_after, limit } => to_json_binary(&query_raffles_by_collection(deps, nft_contract, start_after, limit)?),
+        QueryMsg::RafflesEndingBefore { time, status, start_after, limit } => to_json_binary(&query_raffles_ending_before(deps, time, status, start_after, limit)?),
         QueryMsg::Participant { raffle_id, address } => to_json_binary(&query_participant(deps, raffle_id, address)?),
         QueryMsg::Participants { raffle_id, start_after, limit } => to_json_binary(&query_participants(deps, raffle_id, start_after, limit)?),
+        QueryMsg::TicketOwner { raffle_id, index } => to_json_binary(&TicketOwnerResponse { raffle_id, index, owner: ticket_owner(deps.storage, raffle_id, index)? }),
+        QueryMsg::TicketsOf { raffle_id, address } => to_json_binary(&query_tickets_of(deps, raffle_id, address)?),
+        QueryMsg::UserRaffles { address, start_after, limit } => to_json_binary(&query_user_raffles(deps, address, start_after, limit)?),
+        QueryMsg::UserWins { address } => to_json_binary(&query_user_wins(deps, address)?),
+        QueryMsg::Refund { raffle_id, address } => to_json_binary(&query_refund(deps, env.clone(), raffle_id, address)?),
+        QueryMsg::Ownership {} => to_json_binary(&query_ownership(deps)?),
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::CollectedFees {} => to_json_binary(&query_collected_fees(deps)?),
+        QueryMsg::DrandChain { chain_hash } => to_json_binary(&DrandChainResponse { chain: DRAND_CHAINS.may_load(deps.storage, &chain_hash)? }),
+        QueryMsg::DrandChains { start_after, limit } => to_json_binary(&query_drand_chains(deps, start_after, limit)?),
+        QueryMsg::SeriesPot { creator, series } => to_json_binary(&query_series_pot(deps, creator, series)?),
+        QueryMsg::Draw { raffle_id } => to_json_binary(&DrawResponse { draw: DRAWS.may_load(deps.storage, raffle_id)? }),
+        QueryMsg::VerifyDraw { raffle_id } => to_json_binary(&query_verify_draw(deps, env, raffle_id)?),
     }
 }
 
 fn query_raffle(deps: Deps, raffle_id: u64) -> StdResult<RaffleResponse> {
     let r = RAFFLES.may_load(deps.storage, raffle_id)?;
-    let raffle_view = r.map(|r| RaffleView {
-        id: r.id,
-        creator: r.creator,
-        nft_contract: r.nft_contract,
-        token_id: r.token_id,
-        price: r.price,
-        max_tickets: r.max_tickets,
-        total_sold: r.total_sold,
-        start_time: r.start_time,
-        end_time: r.end_time,
-        status: match r.status { RaffleStatus::Active => "active".into(), RaffleStatus::Completed => "completed".into(), RaffleStatus::Cancelled => "cancelled".into() },
-        winner: r.winner,
-    });
+    let raffle_view = r.map(|r| raffle_view(deps, r)).transpose()?;
     Ok(RaffleResponse { raffle: raffle_view })
 }
 
-fn query_raffles(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
+fn query_raffles(deps: Deps, start_after: Option<String>, limit: Option<u32>, status: Option<RaffleStatus>) -> StdResult<RaffleListResponse> {
+    if let Some(status) = status {
+        return query_raffles_by_status(deps, status, start_after, limit);
+    }
     let start = start_after
         .and_then(|s| s.parse::<u64>().ok())
         .map(Bound::exclusive);
+    let iter = RAFFLES.range(deps.storage, start, None, cosmwasm_std::Order::Ascending);
+    raffle_page(deps, iter, limit)
+}
+
+fn query_raffles_by_creator(deps: Deps, creator: String, start_after: Option<String>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let start = start_after.and_then(|s| s.parse::<u64>().ok()).map(Bound::exclusive);
+    let iter = RAFFLES.idx.creator
+        .prefix(creator)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending);
+    raffle_page(deps, iter, limit)
+}
+
+fn query_raffles_by_status(deps: Deps, status: RaffleStatus, start_after: Option<String>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
+    let start = start_after.and_then(|s| s.parse::<u64>().ok()).map(Bound::exclusive);
+    let iter = RAFFLES.idx.status
+        .prefix(status_key(&status))
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending);
+    raffle_page(deps, iter, limit)
+}
+
+fn query_raffles_by_collection(deps: Deps, nft_contract: String, start_after: Option<String>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
+    let nft_contract = deps.api.addr_validate(&nft_contract)?;
+    let start = start_after.and_then(|s| s.parse::<u64>().ok()).map(Bound::exclusive);
+    let iter = RAFFLES.idx.collections.entries
+        .prefix(nft_contract.as_str())
+        .keys(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .map(|id| {
+            let id = id?;
+            Ok((id, RAFFLES.load(deps.storage, id)?))
+        });
+    raffle_page(deps, iter, limit)
+}
+
+fn query_raffles_ending_before(deps: Deps, time: Timestamp, status: Option<RaffleStatus>, start_after: Option<String>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
+    // Resume after the (end_time, id) of the previous page's last raffle
+    let start = match start_after.and_then(|s| s.parse::<u64>().ok()) {
+        Some(id) => Some(Bound::exclusive((RAFFLES.load(deps.storage, id)?.end_time.nanos(), id))),
+        None => None,
+    };
+    let end = Some(Bound::exclusive((time.nanos(), 0)));
+    match status {
+        Some(status) => {
+            let iter = RAFFLES.idx.status_end_time
+                .sub_prefix(status_key(&status))
+                .range(deps.storage, start, end, cosmwasm_std::Order::Ascending);
+            raffle_page(deps, iter, limit)
+        }
+        None => {
+            let iter = RAFFLES.idx.end_time
+                .range(deps.storage, start, end, cosmwasm_std::Order::Ascending);
+            raffle_page(deps, iter, limit)
+        }
+    }
+}
+
+fn raffle_page(deps: Deps, iter: impl Iterator<Item = StdResult<(u64, Raffle)>>, limit: Option<u32>) -> StdResult<RaffleListResponse> {
     let lim = limit.unwrap_or(50).min(100) as usize;
-    let raffles: StdResult<Vec<_>> = RAFFLES
-        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+    let raffles: StdResult<Vec<_>> = iter
         .take(lim)
         .map(|item| {
             let (_k, r) = item?;
-            Ok(RaffleView {
-                id: r.id,
-                creator: r.creator,
-                nft_contract: r.nft_contract,
-                token_id: r.token_id,
-                price: r.price,
-                max_tickets: r.max_tickets,
-                total_sold: r.total_sold,
-                start_time: r.start_time,
-                end_time: r.end_time,
-                status: match r.status { RaffleStatus::Active => "active".into(), RaffleStatus::Completed => "completed".into(), RaffleStatus::Cancelled => "cancelled".into() },
-                winner: r.winner,
-            })
+            raffle_view(deps, r)
         })
         .collect();
     Ok(RaffleListResponse { raffles: raffles? })
 }
 
+fn raffle_view(deps: Deps, r: Raffle) -> StdResult<RaffleView> {
+    let bounty = raffle_bounty(&r, &CONFIG.load(deps.storage)?);
+    // Time-closed raffles settle on the first round after end_time
+    let drand_round = match r.drand_round {
+        Some(round) => Some(round),
+        None => DRAND_CHAINS
+            .may_load(deps.storage, &r.drand_chain)?
+            .map(|chain| drand_round_after(&chain, r.end_time)),
+    };
+    let (nft_contract, token_id) = r.prizes
+        .iter()
+        .find_map(|p| match p {
+            Prize::Nft { contract, token_id } => Some((contract.clone(), token_id.clone())),
+            _ => None,
+        })
+        .unzip();
+    let winners = r.winners
+        .iter()
+        .filter_map(|w| r.prizes.get(w.prize as usize).map(|p| (p.clone(), w.winner.clone(), w.ticket_index)))
+        .collect();
+    Ok(RaffleView {
+        id: r.id,
+        creator: r.creator,
+        nft_contract,
+        token_id,
+        prizes: r.prizes,
+        nft_escrow: r.nft_escrow,
+        price: r.price,
+        max_tickets: r.max_tickets,
+        min_tickets: r.min_tickets,
+        max_tickets_per_wallet: r.max_tickets_per_wallet,
+        max_tickets_per_tx: r.max_tickets_per_tx,
+        total_sold: r.total_sold,
+        start_time: r.start_time,
+        end_time: r.end_time,
+        randomness_source: r.randomness_source,
+        drand_chain: r.drand_chain,
+        drand_round,
+        status: match r.status { RaffleStatus::Active => "active".into(), RaffleStatus::Completed => "completed".into(), RaffleStatus::Cancelled => "cancelled".into(), RaffleStatus::Expired => "expired".into(), RaffleStatus::Failed => "failed".into() },
+        winner: r.winners.first().map(|w| w.winner.clone()),
+        winners,
+        pot: r.pot,
+        protocol_fee_bps: match r.protocol_fee_bps {
+            Some(bps) => bps,
+            None => CONFIG.load(deps.storage)?.protocol_fee_bps,
+        },
+        bounty,
+    })
+}
+
 fn query_participant(deps: Deps, raffle_id: u64, address: String) -> StdResult<ParticipantResponse> {
     let addr = deps.api.addr_validate(&address)?;
     let count = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &addr))?.unwrap_or(0);
-    Ok(ParticipantResponse { raffle_id, address: addr, ticket_count: count as u32 })
+    let raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    let mut remaining_allowance = raffle.max_tickets.saturating_sub(raffle.total_sold);
+    if let Some(max) = raffle.max_tickets_per_wallet {
+        remaining_allowance = remaining_allowance.min(max.saturating_sub(count));
+    }
+    Ok(ParticipantResponse { raffle_id, address: addr, ticket_count: count, odds_bps: odds_bps(count, raffle.total_sold), remaining_allowance })
+}
+
+/// A holder's chance of winning a single draw, in basis points (rounded down)
+fn odds_bps(ticket_count: u64, total_sold: u64) -> u16 {
+    if total_sold == 0 { return 0; }
+    (ticket_count as u128 * 10_000 / total_sold as u128) as u16
+}
+
+fn query_refund(deps: Deps, env: Env, raffle_id: u64, address: String) -> StdResult<RefundResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    let ticket_count = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &addr))?.unwrap_or(0);
+    let claimed = REFUNDS_CLAIMED.has(deps.storage, (raffle_id, &addr));
+    Ok(RefundResponse {
+        raffle_id,
+        eligible: (refunds_open(&raffle) || reveal_expired(&raffle, env.block.time)) && ticket_count > 0 && !claimed,
+        claimed,
+        ticket_count,
+        amount: raffle.price.amount.checked_mul(Uint128::from(ticket_count))?,
+        denom: payment_denom(&raffle),
+        address: addr,
+    })
 }
 
 fn query_participants(deps: Deps, raffle_id: u64, start_after: Option<String>, limit: Option<u32>) -> StdResult<ParticipantsResponse> {
     let lim = limit.unwrap_or(50).min(200) as usize;
-    let mut out: Vec<(Addr, u64)> = Vec::new();
-    // Iterate all tickets and aggregate (simple version; optimize later)
-    let last: u64 = if let Some(sa) = start_after { sa.parse().unwrap_or(0) } else { 0 };
-    for idx in last..(last + lim as u64) {
-        if let Some(owner) = TICKETS.may_load(deps.storage, (raffle_id, idx))? {
-            out.push((owner, 1));
-        } else {
-            break;
+    let total_sold = RAFFLES.load(deps.storage, raffle_id)?.total_sold;
+    let start_addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    let out: StdResult<Vec<ParticipantEntry>> = USER_TICKET_COUNT
+        .prefix(raffle_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(lim)
+        .map(|item| item.map(|(address, ticket_count)| ParticipantEntry {
+            address,
+            ticket_count,
+            odds_bps: odds_bps(ticket_count, total_sold),
+        }))
+        .collect();
+    Ok(ParticipantsResponse { raffle_id, participants: out? })
+}
+
+fn query_tickets_of(deps: Deps, raffle_id: u64, address: String) -> StdResult<TicketsOfResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let ranges: StdResult<Vec<_>> = OWNER_TICKET_RANGES
+        .prefix((raffle_id, &addr))
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(first_ticket, count)| OwnedTicketRange { first_ticket, last_ticket: first_ticket + count - 1 }))
+        .collect();
+    let ticket_count = USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &addr))?.unwrap_or(0);
+    Ok(TicketsOfResponse { raffle_id, address: addr, ticket_count, ranges: ranges? })
+}
+
+fn query_user_raffles(deps: Deps, address: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<UserRafflesResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let lim = limit.unwrap_or(50).min(200) as usize;
+    let raffles: StdResult<Vec<_>> = USER_RAFFLES
+        .prefix(&addr)
+        .keys(deps.storage, start_after.map(Bound::exclusive), None, cosmwasm_std::Order::Ascending)
+        .take(lim)
+        .map(|raffle_id| {
+            let raffle_id = raffle_id?;
+            Ok(UserRaffleEntry {
+                raffle_id,
+                ticket_count: USER_TICKET_COUNT.may_load(deps.storage, (raffle_id, &addr))?.unwrap_or(0),
+                status: RAFFLES.load(deps.storage, raffle_id)?.status,
+            })
+        })
+        .collect();
+    Ok(UserRafflesResponse { address: addr, raffles: raffles? })
+}
+
+fn query_user_wins(deps: Deps, address: String) -> StdResult<UserWinsResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let wins: StdResult<Vec<_>> = USER_WINS
+        .prefix(&addr)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| {
+            let (raffle_id, prize_index) = item?;
+            let raffle = RAFFLES.load(deps.storage, raffle_id)?;
+            let ticket_index = raffle.winners
+                .iter()
+                .find(|w| w.prize == prize_index)
+                .and_then(|w| w.ticket_index);
+            let prize = raffle.prizes
+                .get(prize_index as usize)
+                .cloned()
+                .ok_or_else(|| StdError::not_found("prize"))?;
+            Ok(UserWin { raffle_id, prize_index, prize, ticket_index })
+        })
+        .collect();
+    Ok(UserWinsResponse { address: addr, wins: wins? })
+}
+
+fn query_series_pot(deps: Deps, creator: String, series: String) -> StdResult<SeriesPotResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let pots: StdResult<Vec<_>> = SERIES_POTS
+        .prefix((&creator, series.as_str()))
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect();
+    Ok(SeriesPotResponse { creator, series, pots: pots? })
+}
+
+fn query_verify_draw(deps: Deps, env: Env, raffle_id: u64) -> StdResult<VerifyDrawResponse> {
+    let draw = DRAWS.load(deps.storage, raffle_id)?;
+    let mut raffle = RAFFLES.load(deps.storage, raffle_id)?;
+    let proof_valid = match &draw.proof {
+        DrawProof::Drand { chain, round, signature, previous_signature } => {
+            let chain = DRAND_CHAINS.load(deps.storage, chain)?;
+            verify_drand_signature(&chain, *round, &draw.randomness, signature, previous_signature.as_deref())
+                .unwrap_or(false)
         }
-    }
-    Ok(ParticipantsResponse { raffle_id, participants: out })
+        DrawProof::CommitReveal { secret, entropy } => match (&raffle.randomness_source, hex::decode(secret), hex::decode(entropy)) {
+            (RandomnessSource::CommitReveal { commitment, .. }, Ok(secret), Ok(entropy)) => {
+                let mut hasher = Sha256::new();
+                hasher.update(&secret);
+                hasher.update(&entropy);
+                hex::encode(Sha256::digest(&secret)) == *commitment
+                    && hex::encode(hasher.finalize()) == draw.randomness
+            }
+            _ => false,
+        },
+    };
+
+    let rnd_bytes = hex::decode(&draw.randomness).map_err(|_| StdError::generic_err("invalid stored randomness"))?;
+    // Re-derive with the recorded ticket count over the stored ticket ranges
+    raffle.total_sold = draw.total_sold;
+    let recomputed = draw_winners(deps.storage, &env.contract.address, &raffle, &rnd_bytes)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(VerifyDrawResponse {
+        raffle_id,
+        proof_valid,
+        winners_match: recomputed == draw.winners,
+        recomputed,
+    })
 }
 
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
@@ -417,42 +1416,258 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         admin: c.admin,
         protocol_fee_bps: c.protocol_fee_bps,
         bounty_amount: c.bounty_amount,
-        drand_pubkey: c.drand_pubkey,
-        drand_round_seconds: c.drand_round_seconds,
+        default_drand_chain: c.default_drand_chain,
+        paused: c.paused,
     })
 }
 
+fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let c = CONFIG.load(deps.storage)?;
+    let operators: StdResult<Vec<_>> = [OperatorRole::Pauser, OperatorRole::FeeManager, OperatorRole::BeaconManager]
+        .into_iter()
+        .flat_map(|role| {
+            OPERATORS
+                .prefix(role.key())
+                .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                .map(move |addr| addr.map(|addr| (role.clone(), addr)))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    Ok(OwnershipResponse { admin: c.admin, pending_admin: c.pending_admin, operators: operators? })
+}
+
+fn query_collected_fees(deps: Deps) -> StdResult<CollectedFeesResponse> {
+    let fees: StdResult<Vec<_>> = COLLECTED_FEES
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| {
+            let (denom, bal) = item?;
+            Ok(CollectedFee { denom, cw20: bal.cw20, amount: bal.amount })
+        })
+        .collect();
+    Ok(CollectedFeesResponse { fees: fees? })
+}
+
+fn query_drand_chains(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<DrandChainsResponse> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let lim = limit.unwrap_or(50).min(100) as usize;
+    let chains: StdResult<Vec<_>> = DRAND_CHAINS
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(lim)
+        .map(|item| item.map(|(_k, c)| c))
+        .collect();
+    Ok(DrandChainsResponse { chains: chains? })
+}
+
 fn exec_update_config(
     deps: DepsMut,
     info: MessageInfo,
     fee_bps: Option<u16>,
     bounty: Option<Coin>,
-    drand_pubkey: Option<String>,
-    drand_round_seconds: Option<u64>,
-) -> StdResult<Response> {
-    CONFIG.update(deps.storage, |mut c| -> StdResult<_> {
-        if info.sender != c.admin { return Err(StdError::generic_err("unauthorized")); }
+    default_drand_chain: Option<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if fee_bps.is_some() || bounty.is_some() {
+        ensure_role(deps.storage, &cfg, &info.sender, OperatorRole::FeeManager)?;
+    }
+    if let Some(v) = fee_bps { ensure_fee_bps(v)?; }
+    let default_drand_chain = match default_drand_chain {
+        Some(chain_hash) => {
+            ensure_role(deps.storage, &cfg, &info.sender, OperatorRole::BeaconManager)?;
+            Some(load_active_drand_chain(deps.as_ref(), &chain_hash)?.chain_hash)
+        }
+        None => None,
+    };
+    if fee_bps.is_none() && bounty.is_none() && default_drand_chain.is_none() {
+        ensure_admin(&cfg, &info.sender)?;
+    }
+    CONFIG.update(deps.storage, |mut c| -> Result<_, ContractError> {
         if let Some(v) = fee_bps { c.protocol_fee_bps = v; }
         if bounty.is_some() { c.bounty_amount = bounty; }
-        if drand_pubkey.is_some() { c.drand_pubkey = drand_pubkey; }
-        if drand_round_seconds.is_some() { c.drand_round_seconds = drand_round_seconds; }
+        if default_drand_chain.is_some() { c.default_drand_chain = default_drand_chain; }
         Ok(c)
     })?;
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
-fn exec_withdraw_fees(_deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
-    // Placeholder; track fees in full implementation
-    Ok(Response::new().add_attribute("action", "withdraw_fees").add_attribute("caller", info.sender))
+fn ensure_fee_bps(fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > 10_000 { return Err(ContractError::InvalidFeeBps { fee_bps }); }
+    Ok(())
+}
+
+fn exec_add_drand_chain(deps: DepsMut, info: MessageInfo, chain: DrandChainMsg) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &cfg, &info.sender, OperatorRole::BeaconManager)?;
+    let chain = validate_drand_chain(chain)?;
+    // Chain parameters are immutable once registered, raffles pinned to them rely on it
+    if DRAND_CHAINS.has(deps.storage, &chain.chain_hash) { return Err(ContractError::DrandChainExists { chain_hash: chain.chain_hash }); }
+    DRAND_CHAINS.save(deps.storage, &chain.chain_hash, &chain)?;
+    Ok(Response::new()
+        .add_attribute("action", "add_drand_chain")
+        .add_attribute("chain_hash", chain.chain_hash)
+        .add_attribute("period", chain.period.to_string()))
+}
+
+fn exec_set_drand_chain_active(deps: DepsMut, info: MessageInfo, chain_hash: String, active: bool) -> Result<Response, ContractError> {
+    let chain_hash = chain_hash.to_lowercase();
+    let cfg = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &cfg, &info.sender, OperatorRole::BeaconManager)?;
+    if !active && cfg.default_drand_chain.as_deref() == Some(chain_hash.as_str()) {
+        return Err(ContractError::DeactivateDefaultDrandChain {});
+    }
+    DRAND_CHAINS.update(deps.storage, &chain_hash, |c| -> Result<_, ContractError> {
+        let mut c = c.ok_or_else(|| ContractError::UnknownDrandChain { chain_hash: chain_hash.clone() })?;
+        c.active = active;
+        Ok(c)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_drand_chain_active")
+        .add_attribute("chain_hash", chain_hash)
+        .add_attribute("active", active.to_string()))
+}
+
+/// Chain hashes are stored lowercase, so any casing of a registered hash is accepted
+fn load_active_drand_chain(deps: Deps, chain_hash: &str) -> Result<DrandChain, ContractError> {
+    let chain_hash = chain_hash.to_lowercase();
+    let chain = DRAND_CHAINS
+        .may_load(deps.storage, &chain_hash)?
+        .ok_or_else(|| ContractError::UnknownDrandChain { chain_hash: chain_hash.clone() })?;
+    if !chain.active { return Err(ContractError::DrandChainInactive { chain_hash }); }
+    Ok(chain)
+}
+
+pub(crate) fn validate_drand_chain(msg: DrandChainMsg) -> Result<DrandChain, ContractError> {
+    let chain_hash = msg.chain_hash.to_lowercase();
+    if hex::decode(&chain_hash).map(|h| h.len()) != Ok(32) {
+        return Err(ContractError::InvalidDrandChainHash {});
+    }
+    if msg.period == 0 { return Err(ContractError::ZeroDrandPeriod {}); }
+    let pubkey = msg.pubkey.to_lowercase();
+    // Parse now so a bad key is rejected at registration rather than at the draw
+    match msg.scheme {
+        DrandScheme::Chained | DrandScheme::Unchained => { parse_g1(&pubkey, "drand public key")?; }
+        DrandScheme::UnchainedG1 => { parse_g2(&pubkey, "drand public key")?; }
+    }
+    Ok(DrandChain {
+        chain_hash,
+        pubkey,
+        genesis_time: msg.genesis_time,
+        period: msg.period,
+        scheme: msg.scheme,
+        active: true,
+    })
+}
+
+fn exec_withdraw_fees(deps: DepsMut, info: MessageInfo, to: String, denoms: Vec<String>, amounts: Vec<Uint128>) -> Result<Response, ContractError> {
+    // Treasury action, never delegated to operators
+    let cfg = CONFIG.load(deps.storage)?;
+    ensure_admin(&cfg, &info.sender)?;
+    if denoms.is_empty() || denoms.len() != amounts.len() { return Err(ContractError::InvalidWithdrawal {}); }
+    let to = deps.api.addr_validate(&to)?;
+
+    let mut resp = Response::new()
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("caller", info.sender)
+        .add_attribute("to", to.to_string());
+    for (denom, amount) in denoms.into_iter().zip(amounts) {
+        if amount.is_zero() { return Err(ContractError::ZeroWithdrawAmount {}); }
+        let mut bal = COLLECTED_FEES
+            .may_load(deps.storage, &denom)?
+            .unwrap_or(FeeBalance { cw20: false, amount: Uint128::zero() });
+        if bal.amount < amount {
+            return Err(ContractError::InsufficientFees { denom, requested: amount, available: bal.amount });
+        }
+        bal.amount -= amount;
+        COLLECTED_FEES.save(deps.storage, &denom, &bal)?;
+        resp = resp.add_message(send_msg(&denom, bal.cw20, &to, amount)?)
+            .add_attribute("withdrawn", format!("{}{}", amount, denom));
+    }
+    Ok(resp)
+}
+
+fn ensure_admin(cfg: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if cfg.admin.as_ref() != Some(sender) { return Err(ContractError::Unauthorized {}); }
+    Ok(())
+}
+
+/// The admin, or an operator holding `role`
+fn ensure_role(storage: &dyn Storage, cfg: &Config, sender: &Addr, role: OperatorRole) -> Result<(), ContractError> {
+    if cfg.admin.as_ref() == Some(sender) || OPERATORS.has(storage, (role.key(), sender)) { return Ok(()); }
+    Err(ContractError::Unauthorized {})
+}
+
+fn exec_propose_admin(deps: DepsMut, info: MessageInfo, address: String) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    ensure_admin(&cfg, &info.sender)?;
+    let pending = deps.api.addr_validate(&address)?;
+    cfg.pending_admin = Some(pending.clone());
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_admin")
+        .add_attribute("pending_admin", pending))
+}
+
+fn exec_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let pending = cfg.pending_admin.take().ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending { return Err(ContractError::Unauthorized {}); }
+    cfg.admin = Some(pending.clone());
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", pending))
+}
+
+fn exec_renounce_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    ensure_admin(&cfg, &info.sender)?;
+    // WithdrawFees is admin-only, so anything still collected would be locked for good
+    let unwithdrawn = COLLECTED_FEES
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .find(|entry| entry.as_ref().map_or(true, |(_, bal)| !bal.amount.is_zero()))
+        .transpose()?;
+    if let Some((denom, _)) = unwithdrawn { return Err(ContractError::FeesNotWithdrawn { denom }); }
+    cfg.admin = None;
+    cfg.pending_admin = None;
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(Response::new()
+        .add_attribute("action", "renounce_admin")
+        .add_attribute("previous_admin", info.sender))
+}
+
+fn exec_set_operator(deps: DepsMut, info: MessageInfo, role: OperatorRole, address: String, enabled: bool) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    ensure_admin(&cfg, &info.sender)?;
+    let operator = deps.api.addr_validate(&address)?;
+    if enabled {
+        OPERATORS.save(deps.storage, (role.key(), &operator), &true)?;
+    } else {
+        OPERATORS.remove(deps.storage, (role.key(), &operator));
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_operator")
+        .add_attribute("role", role.key())
+        .add_attribute("operator", operator)
+        .add_attribute("enabled", enabled.to_string()))
 }
 
+fn exec_set_paused(deps: DepsMut, info: MessageInfo, paused: bool) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    ensure_role(deps.storage, &cfg, &info.sender, OperatorRole::Pauser)?;
+    cfg.paused = paused;
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
 
-// Drand League of Entropy mainnet public key
-const DRAND_MAINNET_PUBKEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
+fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.paused { return Err(ContractError::Paused {}); }
+    Ok(())
+}
 
-// Drand network parameters
-const DRAND_GENESIS_TIME: u64 = 1595431050; // League of Entropy mainnet genesis time
-const DRAND_PERIOD: u64 = 30; // 30 seconds per round
+// Hash-to-curve domain separation tags used by drand
+const DRAND_G2_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const DRAND_G1_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
 
 /// Calculate current drand round based on genesis time
 fn current_drand_round(current_time: u64, genesis_time: u64, period: u64) -> u64 {
@@ -462,120 +1677,121 @@ fn current_drand_round(current_time: u64, genesis_time: u64, period: u64) -> u64
     ((current_time - genesis_time) / period) + 1
 }
 
+/// First drand round published strictly after `time`, its randomness was unknown at that time
+pub(crate) fn drand_round_after(chain: &DrandChain, time: Timestamp) -> u64 {
+    current_drand_round(time.seconds(), chain.genesis_time, chain.period) + 1
+}
+
 /// Verify drand BLS signature using proper BLS12-381 pairing verification
 fn verify_drand_signature(
-    env: &Env,
-    drand_pubkey: &str,
+    chain: &DrandChain,
     drand_round: u64,
     randomness: &str,
     signature: &str,
-) -> StdResult<bool> {
+    previous_signature: Option<&str>,
+) -> Result<bool, ContractError> {
     // Validate inputs
-    if drand_pubkey.is_empty() || randomness.is_empty() || signature.is_empty() {
+    if randomness.is_empty() || signature.is_empty() {
         return Ok(false);
     }
     
-    // Check if using the correct drand public key
-    if drand_pubkey != DRAND_MAINNET_PUBKEY {
-        return Err(StdError::generic_err("invalid drand public key - must use League of Entropy mainnet"));
-    }
-    
     // Validate randomness format (should be 64 hex characters = 32 bytes)
     if randomness.len() != 64 {
         return Ok(false);
     }
     
-    // Parse the drand public key (G1 point, 48 bytes compressed)
-    let pubkey_bytes = hex::decode(drand_pubkey)
-        .map_err(|_| StdError::generic_err("invalid drand public key hex"))?;
-    
-    if pubkey_bytes.len() != 48 {
-        return Err(StdError::generic_err("invalid drand public key length - must be 48 bytes"));
-    }
-    
-    let mut pubkey_array = [0u8; 48];
-    pubkey_array.copy_from_slice(&pubkey_bytes);
-    
-    let _pubkey = G1Affine::from_compressed(&pubkey_array)
-        .into_option()
-        .ok_or_else(|| StdError::generic_err("invalid drand public key format"))?;
-    
-    // Parse the signature (G2 point, 96 bytes compressed)
+    // Drand randomness = SHA256(signature_bytes)
     let sig_bytes = hex::decode(signature)
-        .map_err(|_| StdError::generic_err("invalid signature hex"))?;
-    
-    if sig_bytes.len() != 96 {
-        return Err(StdError::generic_err("invalid signature length - must be 96 bytes"));
-    }
-    
-    let mut sig_array = [0u8; 96];
-    sig_array.copy_from_slice(&sig_bytes);
-    
-    let _sig_point = G2Affine::from_compressed(&sig_array)
-        .into_option()
-        .ok_or_else(|| StdError::generic_err("invalid signature format"))?;
-    
-    // VERIFY ROUND FRESHNESS (CRITICAL SECURITY CHECK)
-    // Use blockchain time to calculate expected current round
-    let current_time = env.block.time.seconds();
-    
-    let expected_round = current_drand_round(current_time, DRAND_GENESIS_TIME, DRAND_PERIOD);
-    
-    // Allow tolerance for network delays and time sync issues
-    // - Allow rounds up to 10 minutes old (20 rounds)
-    // - Allow rounds up to 5 minutes in future (10 rounds)
-    let max_round_age = 20; // 10 minutes in rounds
-    let max_future_rounds = 10; // 5 minutes in rounds
-    
-    if drand_round < expected_round - max_round_age {
-        return Err(StdError::generic_err(format!(
-            "drand round too old: round={}, expected={}, age={} rounds ({} minutes)",
-            drand_round,
-            expected_round,
-            expected_round - drand_round,
-            (expected_round - drand_round) * 30 / 60
-        )));
-    }
-    
-    if drand_round > expected_round + max_future_rounds {
-        return Err(StdError::generic_err(format!(
-            "drand round too far in future: round={}, expected={}, future={} rounds ({} minutes)",
-            drand_round,
-            expected_round,
-            drand_round - expected_round,
-            (drand_round - expected_round) * 30 / 60
-        )));
-    }
-    
-    // Verify the randomness matches what the signature should produce
+        .map_err(|_| ContractError::InvalidHex { what: "signature".into() })?;
     let randomness_bytes = hex::decode(randomness)
-        .map_err(|_| StdError::generic_err("invalid randomness hex"))?;
-    
-    if randomness_bytes.len() != 32 {
-        return Err(StdError::generic_err("invalid randomness length - must be 32 bytes"));
+        .map_err(|_| ContractError::InvalidHex { what: "randomness".into() })?;
+    if Sha256::digest(&sig_bytes).as_slice() != randomness_bytes.as_slice() {
+        return Err(ContractError::RandomnessMismatch {});
     }
-    
-    // SIMPLIFIED BLS VERIFICATION FOR DRAND
-    // Since the full BLS verification is complex and error-prone,
-    // we'll use a simplified but secure approach:
-    // 1. Verify the signature format is valid (already done above)
-    // 2. Verify randomness consistency: SHA256(signature) == randomness
-    
-    // Drand randomness = SHA256(signature_bytes)
-    // This prevents signature tampering and ensures the randomness matches the signature
+
+    // Chained beacons sign previous_signature || round, unchained ones only the round
+    let prev_sig_bytes = match chain.scheme {
+        DrandScheme::Chained => {
+            let prev = previous_signature
+                .ok_or(ContractError::MissingPreviousSignature {})?;
+            Some(hex::decode(prev).map_err(|_| ContractError::InvalidHex { what: "previous signature".into() })?)
+        }
+        DrandScheme::Unchained | DrandScheme::UnchainedG1 => None,
+    };
+    let message = drand_message(drand_round, prev_sig_bytes.as_deref());
+
+    // Full BLS check: e(pk, H(msg)) == e(g1, sig), with the groups swapped for G1 signatures
+    match chain.scheme {
+        DrandScheme::Chained | DrandScheme::Unchained => {
+            let pubkey = parse_g1(&chain.pubkey, "drand public key")?;
+            let sig_point = parse_g2(signature, "signature")?;
+            Ok(verify_bls_g2_signature(&pubkey, &message, &sig_point))
+        }
+        DrandScheme::UnchainedG1 => {
+            let pubkey = parse_g2(&chain.pubkey, "drand public key")?;
+            let sig_point = parse_g1(signature, "signature")?;
+            Ok(verify_bls_g1_signature(&pubkey, &message, &sig_point))
+        }
+    }
+}
+
+/// Parse a compressed G1 point (48 bytes) from hex.
+fn parse_g1(hex_str: &str, what: &str) -> Result<G1Affine, ContractError> {
+    let bytes = hex::decode(hex_str)
+        .map_err(|_| ContractError::InvalidHex { what: what.to_string() })?;
+    let array: [u8; 48] = bytes
+        .try_into()
+        .map_err(|_| ContractError::InvalidLength { what: what.to_string(), expected: 48 })?;
+    G1Affine::from_compressed(&array)
+        .into_option()
+        .ok_or_else(|| ContractError::InvalidPoint { what: what.to_string() })
+}
+
+/// Parse a compressed G2 point (96 bytes) from hex.
+fn parse_g2(hex_str: &str, what: &str) -> Result<G2Affine, ContractError> {
+    let bytes = hex::decode(hex_str)
+        .map_err(|_| ContractError::InvalidHex { what: what.to_string() })?;
+    let array: [u8; 96] = bytes
+        .try_into()
+        .map_err(|_| ContractError::InvalidLength { what: what.to_string(), expected: 96 })?;
+    G2Affine::from_compressed(&array)
+        .into_option()
+        .ok_or_else(|| ContractError::InvalidPoint { what: what.to_string() })
+}
+
+/// Message signed by drand for a round: SHA256(previous_signature || round_be) for
+/// chained beacons, SHA256(round_be) for unchained ones.
+fn drand_message(round: u64, previous_signature: Option<&[u8]>) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(&sig_bytes);
-    let computed_randomness = hasher.finalize();
-    
-    // Compare computed randomness with provided randomness
-    if computed_randomness.as_slice() != randomness_bytes.as_slice() {
-        return Err(StdError::generic_err("randomness does not match signature - possible tampering detected"));
+    if let Some(prev) = previous_signature {
+        hasher.update(prev);
     }
-    
-    // For now, we'll accept the signature if:
-    // 1. Format is valid (already checked)
-    // 2. Randomness matches signature (checked above)
-    // 3. Round is within acceptable range (checked above)
-    
-    Ok(true)
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Verify a BLS signature with the public key on G1 and the signature on G2,
+/// i.e. e(pk, H(msg)) == e(g1, sig), checked as e(-pk, H(msg)) * e(g1, sig) == 1.
+fn verify_bls_g2_signature(pubkey: &G1Affine, message: &[u8], signature: &G2Affine) -> bool {
+    let msg_point = <G2Projective as HashToCurve<ExpandMsgXmd<sha2_v09::Sha256>>>::hash_to_curve(message, DRAND_G2_DST);
+    let msg_prepared = G2Prepared::from(G2Affine::from(msg_point));
+    let sig_prepared = G2Prepared::from(*signature);
+    let neg_pubkey = -pubkey;
+    multi_miller_loop(&[(&neg_pubkey, &msg_prepared), (&G1Affine::generator(), &sig_prepared)])
+        .final_exponentiation()
+        == Gt::identity()
 }
+
+/// Verify a BLS signature with the public key on G2 and the signature on G1 (quicknet),
+/// i.e. e(H(msg), pk) == e(sig, g2), checked as e(H(msg), pk) * e(-sig, g2) == 1.
+fn verify_bls_g1_signature(pubkey: &G2Affine, message: &[u8], signature: &G1Affine) -> bool {
+    let msg_point = <G1Projective as HashToCurve<ExpandMsgXmd<sha2_v09::Sha256>>>::hash_to_curve(message, DRAND_G1_DST);
+    let msg_affine = G1Affine::from(msg_point);
+    let pk_prepared = G2Prepared::from(*pubkey);
+    let g2_prepared = G2Prepared::from(G2Affine::generator());
+    let neg_sig = -signature;
+    multi_miller_loop(&[(&msg_affine, &pk_prepared), (&neg_sig, &g2_prepared)])
+        .final_exponentiation()
+        == Gt::identity()
+}
+
@@ -0,0 +1,538 @@
+use super::*;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{coins, from_json, ContractResult, OwnedDeps, ReplyOn, SubMsgResult, SystemResult, WasmQuery};
+use crate::msg::*;
+
+// League of Entropy mainnet (pedersen-bls-chained), round 72785
+const MAINNET_HASH: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+const MAINNET_PUBKEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
+const MAINNET_GENESIS: u64 = 1595431050;
+const MAINNET_ROUND: u64 = 72785;
+const MAINNET_SIG: &str = "82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42";
+const MAINNET_PREV_SIG: &str = "a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747";
+const MAINNET_RANDOMNESS: &str = "8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9";
+
+// quicknet (bls-unchained-g1-rfc9380), round 1000
+const QUICKNET_HASH: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
+const QUICKNET_PUBKEY: &str = "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a";
+const QUICKNET_SIG: &str = "b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39";
+
+type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+fn mainnet() -> DrandChainMsg {
+    DrandChainMsg { chain_hash: MAINNET_HASH.into(), pubkey: MAINNET_PUBKEY.into(), genesis_time: MAINNET_GENESIS, period: 30, scheme: DrandScheme::Chained }
+}
+
+fn quicknet() -> DrandChainMsg {
+    DrandChainMsg { chain_hash: QUICKNET_HASH.into(), pubkey: QUICKNET_PUBKEY.into(), genesis_time: 1692803367, period: 3, scheme: DrandScheme::UnchainedG1 }
+}
+
+// Every queried token is owned by "creator" and approved to the contract
+fn mock_nft_owner(deps: &mut TestDeps) {
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { .. } => {
+            let owner = cw721::OwnerOfResponse {
+                owner: "creator".into(),
+                approvals: vec![cw721::Approval { spender: MOCK_CONTRACT_ADDR.into(), expires: cw721::Expiration::Never {} }],
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&owner).unwrap()))
+        }
+        _ => panic!("unexpected query"),
+    });
+}
+
+fn setup() -> TestDeps {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: Some("admin".into()),
+        protocol_fee_bps: 0,
+        bounty_amount: None,
+        drand_chains: vec![mainnet()],
+        default_drand_chain: Some(MAINNET_HASH.into()),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    deps
+}
+
+fn env_at(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(seconds);
+    env
+}
+
+// Closes just before round MAINNET_ROUND is published, so the draw settles on it
+fn mainnet_end() -> u64 {
+    MAINNET_GENESIS + (MAINNET_ROUND - 2) * 30 + 5
+}
+
+fn raffle_params(end: u64, source: Option<RandomnessSource>) -> CreateRaffleNftMsg {
+    CreateRaffleNftMsg {
+        price: Coin::new(100, "ucore"),
+        max_tickets: 100,
+        min_tickets: None,
+        max_tickets_per_wallet: None,
+        max_tickets_per_tx: None,
+        start_time: None,
+        end_time: Timestamp::from_seconds(end),
+        revenue_address: None,
+        payment_cw20: None,
+        drand_chain: None,
+        randomness_source: source,
+    }
+}
+
+fn create_coin_raffle(deps: &mut TestDeps, now: u64, params: CreateRaffleNftMsg) -> u64 {
+    let res = execute(deps.as_mut(), env_at(now), mock_info("creator", &coins(1000, "uatom")), ExecuteMsg::CreateCoinRaffle(params)).unwrap();
+    let id = res.attributes.iter().find(|a| a.key == "raffle_id").unwrap();
+    id.value.parse().unwrap()
+}
+
+fn buy(deps: &mut TestDeps, now: u64, raffle_id: u64, buyer: &str, count: u64) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env_at(now), mock_info(buyer, &coins(100 * count as u128, "ucore")), ExecuteMsg::BuyTickets { raffle_id, count, entropy: None })
+}
+
+fn end_mainnet(deps: &mut TestDeps, raffle_id: u64) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::EndRaffle {
+        raffle_id,
+        drand_round: MAINNET_ROUND,
+        randomness: MAINNET_RANDOMNESS.into(),
+        signature: MAINNET_SIG.into(),
+        previous_signature: Some(MAINNET_PREV_SIG.into()),
+    };
+    execute(deps.as_mut(), env_at(mainnet_end() + 60), mock_info("keeper", &[]), msg)
+}
+
+#[test]
+fn verifies_mainnet_chained_beacon() {
+    let chain = validate_drand_chain(mainnet()).unwrap();
+    assert!(verify_drand_signature(&chain, MAINNET_ROUND, MAINNET_RANDOMNESS, MAINNET_SIG, Some(MAINNET_PREV_SIG)).unwrap());
+}
+
+#[test]
+fn verifies_quicknet_g1_beacon() {
+    let chain = validate_drand_chain(quicknet()).unwrap();
+    let randomness = hex::encode(Sha256::digest(hex::decode(QUICKNET_SIG).unwrap()));
+    assert!(verify_drand_signature(&chain, 1000, &randomness, QUICKNET_SIG, None).unwrap());
+    // Same signature claimed for another round
+    assert!(!verify_drand_signature(&chain, 1001, &randomness, QUICKNET_SIG, None).unwrap());
+}
+
+#[test]
+fn rejects_tampered_beacons() {
+    let chain = validate_drand_chain(mainnet()).unwrap();
+    // Flipping a bit of previous_signature changes the signed message
+    let mut prev = hex::decode(MAINNET_PREV_SIG).unwrap();
+    prev[10] ^= 1;
+    let tampered = hex::encode(prev);
+    assert!(!verify_drand_signature(&chain, MAINNET_ROUND, MAINNET_RANDOMNESS, MAINNET_SIG, Some(&tampered)).unwrap());
+    // Wrong round
+    assert!(!verify_drand_signature(&chain, MAINNET_ROUND + 1, MAINNET_RANDOMNESS, MAINNET_SIG, Some(MAINNET_PREV_SIG)).unwrap());
+    // Chained beacons need previous_signature
+    assert_eq!(
+        verify_drand_signature(&chain, MAINNET_ROUND, MAINNET_RANDOMNESS, MAINNET_SIG, None).unwrap_err(),
+        ContractError::MissingPreviousSignature {},
+    );
+    // Randomness that isn't sha256(signature)
+    let other = hex::encode([7u8; 32]);
+    assert_eq!(
+        verify_drand_signature(&chain, MAINNET_ROUND, &other, MAINNET_SIG, Some(MAINNET_PREV_SIG)).unwrap_err(),
+        ContractError::RandomnessMismatch {},
+    );
+}
+
+#[test]
+fn end_raffle_draws_distinct_winners_per_prize() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let raffle_id = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+    for _ in 0..2 {
+        execute(deps.as_mut(), env_at(now), mock_info("creator", &coins(500, "uatom")), ExecuteMsg::AddCoinPrize { raffle_id }).unwrap();
+    }
+    buy(&mut deps, now, raffle_id, "alice", 5).unwrap();
+    buy(&mut deps, now, raffle_id, "bob", 1).unwrap();
+    buy(&mut deps, now, raffle_id, "carol", 1).unwrap();
+
+    end_mainnet(&mut deps, raffle_id).unwrap();
+    let raffle = RAFFLES.load(&deps.storage, raffle_id).unwrap();
+    assert!(matches!(raffle.status, RaffleStatus::Completed));
+    assert_eq!(raffle.winners.len(), 3);
+    let mut winners: Vec<_> = raffle.winners.iter().map(|w| w.winner.to_string()).collect();
+    winners.sort();
+    winners.dedup();
+    assert_eq!(winners, vec!["alice", "bob", "carol"]);
+    for w in &raffle.winners {
+        let owner = ticket_owner(&deps.storage, raffle_id, w.ticket_index.unwrap()).unwrap();
+        assert_eq!(owner, Some(w.winner.clone()));
+    }
+
+    let verify: VerifyDrawResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::VerifyDraw { raffle_id }).unwrap()).unwrap();
+    assert!(verify.proof_valid);
+    assert!(verify.winners_match);
+
+    assert_eq!(end_mainnet(&mut deps, raffle_id).unwrap_err(), ContractError::RaffleNotActive { raffle_id });
+}
+
+#[test]
+fn cancel_only_before_sales_and_close() {
+    let mut deps = setup();
+    let now = 1_700_000_000;
+    let cancel = |deps: &mut TestDeps, at: u64, raffle_id: u64| {
+        execute(deps.as_mut(), env_at(at), mock_info("creator", &[]), ExecuteMsg::CancelRaffle { raffle_id })
+    };
+
+    let sold = create_coin_raffle(&mut deps, now, raffle_params(now + 100, None));
+    buy(&mut deps, now, sold, "alice", 3).unwrap();
+    assert_eq!(cancel(&mut deps, now + 200, sold).unwrap_err(), ContractError::TicketsSold { raffle_id: sold });
+    assert_eq!(cancel(&mut deps, now + 1, sold).unwrap_err(), ContractError::TicketsSold { raffle_id: sold });
+
+    let closed = create_coin_raffle(&mut deps, now, raffle_params(now + 100, None));
+    assert_eq!(cancel(&mut deps, now + 100, closed).unwrap_err(), ContractError::RaffleEnded { raffle_id: closed });
+
+    let open = create_coin_raffle(&mut deps, now, raffle_params(now + 100, None));
+    let res = cancel(&mut deps, now + 50, open).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: "creator".into(), amount: coins(1000, "uatom") }));
+    assert!(matches!(RAFFLES.load(&deps.storage, open).unwrap().status, RaffleStatus::Cancelled));
+}
+
+#[test]
+fn missed_reveal_opens_refunds() {
+    let mut deps = setup();
+    let now = 1_700_000_000;
+    let secret = [9u8; 32];
+    let source = RandomnessSource::CommitReveal { commitment: hex::encode(Sha256::digest(secret)), reveal_timeout: 600 };
+    let mut params = raffle_params(now + 100, Some(source));
+    params.revenue_address = Some("treasury".into());
+    let raffle_id = create_coin_raffle(&mut deps, now, params);
+    // Whoever knows the secret can't play
+    for committer in ["creator", "treasury"] {
+        assert_eq!(buy(&mut deps, now, raffle_id, committer, 1).unwrap_err(), ContractError::CommitterCannotBuy { raffle_id });
+    }
+    buy(&mut deps, now, raffle_id, "alice", 3).unwrap();
+
+    // Within the reveal window nothing is refundable
+    let err = execute(deps.as_mut(), env_at(now + 200), mock_info("alice", &[]), ExecuteMsg::ClaimRefund { raffle_id }).unwrap_err();
+    assert_eq!(err, ContractError::NotRefundable { raffle_id });
+
+    let late = env_at(now + 701);
+    let reveal = ExecuteMsg::RevealRandomness { raffle_id, secret: hex::encode(secret) };
+    assert_eq!(execute(deps.as_mut(), late.clone(), mock_info("creator", &[]), reveal).unwrap_err(), ContractError::RevealDeadlinePassed { raffle_id });
+
+    let res = execute(deps.as_mut(), late.clone(), mock_info("alice", &[]), ExecuteMsg::ClaimRefund { raffle_id }).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address: "creator".into(), amount: coins(1000, "uatom") }),
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address: "alice".into(), amount: coins(300, "ucore") }),
+    );
+    assert!(matches!(RAFFLES.load(&deps.storage, raffle_id).unwrap().status, RaffleStatus::Failed));
+    let err = execute(deps.as_mut(), late, mock_info("alice", &[]), ExecuteMsg::ClaimRefund { raffle_id }).unwrap_err();
+    assert_eq!(err, ContractError::RefundAlreadyClaimed {});
+}
+
+#[test]
+fn cw20_purchase_buys_requested_count_and_refunds_change() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let mut params = raffle_params(mainnet_end(), None);
+    params.payment_cw20 = Some("token".into());
+    let id = create_coin_raffle(&mut deps, now, params);
+
+    let receive = |buyer: &str, amount: u128, count: Option<u64>| ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+        sender: buyer.into(),
+        amount: Uint128::new(amount),
+        msg: to_json_binary(&BuyTicketsCw20Msg { raffle_id: id, count, entropy: None }).unwrap(),
+    });
+
+    // 350 tokens for 2 tickets at 100: two tickets, 150 back
+    let res = execute(deps.as_mut(), env_at(now), mock_info("token", &[]), receive("alice", 350, Some(2))).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("quantity", "2")));
+    assert!(res.attributes.contains(&Attribute::new("refund", "150")));
+    let refund: CosmosMsg = WasmMsg::Execute {
+        contract_addr: "token".into(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient: "alice".into(), amount: Uint128::new(150) }).unwrap(),
+        funds: vec![],
+    }.into();
+    assert_eq!(res.messages[0].msg, refund);
+
+    let err = execute(deps.as_mut(), env_at(now), mock_info("token", &[]), receive("bob", 150, Some(2))).unwrap_err();
+    assert_eq!(err, ContractError::InsufficientPayment { required: Uint128::new(200), paid: Uint128::new(150) });
+
+    // Without a count the amount buys floor(amount / price) tickets
+    let res = execute(deps.as_mut(), env_at(now), mock_info("token", &[]), receive("bob", 250, None)).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("quantity", "2")));
+    assert!(res.attributes.contains(&Attribute::new("refund", "50")));
+    let err = execute(deps.as_mut(), env_at(now), mock_info("token", &[]), receive("bob", u128::MAX, None)).unwrap_err();
+    assert_eq!(err, ContractError::ExceedsMaxTickets { requested: u64::MAX, remaining: 96 });
+}
+
+#[test]
+fn raffles_below_min_tickets_fail_without_a_draw() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let mut params = raffle_params(mainnet_end(), None);
+    params.min_tickets = Some(5);
+    let raffle_id = create_coin_raffle(&mut deps, now, params);
+    buy(&mut deps, now, raffle_id, "alice", 2).unwrap();
+
+    let expire = ExecuteMsg::ExpireRaffle { raffle_id };
+    let err = execute(deps.as_mut(), env_at(mainnet_end() - 1), mock_info("keeper", &[]), expire.clone()).unwrap_err();
+    assert_eq!(err, ContractError::RaffleNotReadyToEnd { raffle_id });
+    let res = execute(deps.as_mut(), env_at(mainnet_end() + 60), mock_info("keeper", &[]), expire).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("action", "raffle_failed")));
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address: "creator".into(), amount: coins(1000, "uatom") }),
+    );
+    assert!(matches!(RAFFLES.load(&deps.storage, raffle_id).unwrap().status, RaffleStatus::Failed));
+
+    let res = execute(deps.as_mut(), env_at(mainnet_end() + 90), mock_info("alice", &[]), ExecuteMsg::ClaimRefund { raffle_id }).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address: "alice".into(), amount: coins(200, "ucore") }),
+    );
+
+    // EndRaffle fails such a raffle too, before asking for a beacon
+    let mut params = raffle_params(mainnet_end(), None);
+    params.min_tickets = Some(5);
+    let raffle_id = create_coin_raffle(&mut deps, now, params);
+    buy(&mut deps, now, raffle_id, "bob", 1).unwrap();
+    let no_beacon = ExecuteMsg::EndRaffle { raffle_id, drand_round: 0, randomness: String::new(), signature: String::new(), previous_signature: None };
+    let err = execute(deps.as_mut(), env_at(mainnet_end() - 1), mock_info("keeper", &[]), no_beacon.clone()).unwrap_err();
+    assert_eq!(err, ContractError::RaffleNotReadyToEnd { raffle_id });
+    let res = execute(deps.as_mut(), env_at(mainnet_end() + 60), mock_info("keeper", &[]), no_beacon).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("action", "raffle_failed")));
+    assert!(matches!(RAFFLES.load(&deps.storage, raffle_id).unwrap().status, RaffleStatus::Failed));
+}
+
+#[test]
+fn create_without_coin_prizes_rejects_funds() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let funds = coins(500, "ucore");
+    let lottery = ExecuteMsg::CreateLottery { raffle: raffle_params(mainnet_end(), None), tiers_bps: vec![10_000], series: None };
+    let err = execute(deps.as_mut(), env_at(now), mock_info("creator", &funds), lottery).unwrap_err();
+    assert_eq!(err, ContractError::Payment(cw_utils::PaymentError::NonPayable {}));
+
+    let p = raffle_params(mainnet_end(), None);
+    let direct = ExecuteMsg::CreateRaffle {
+        nft_contract: "collection".into(),
+        token_id: "1".into(),
+        price: p.price,
+        max_tickets: p.max_tickets,
+        min_tickets: None,
+        max_tickets_per_wallet: None,
+        max_tickets_per_tx: None,
+        start_time: None,
+        end_time: p.end_time,
+        revenue_address: None,
+        payment_cw20: None,
+        drand_chain: None,
+        randomness_source: None,
+    };
+    let err = execute(deps.as_mut(), env_at(now), mock_info("creator", &funds), direct).unwrap_err();
+    assert_eq!(err, ContractError::Payment(cw_utils::PaymentError::NonPayable {}));
+}
+
+#[test]
+fn raffle_indexes_cover_every_collection_and_status_end_time() {
+    let mut deps = setup();
+    let now = 1_700_000_000;
+    let receive_nft = |token_id: &str, msg: Binary| ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
+        sender: "creator".into(),
+        token_id: token_id.into(),
+        msg,
+    });
+    let ids_of = |res: RaffleListResponse| res.raffles.iter().map(|r| r.id).collect::<Vec<_>>();
+
+    // Raffle 1 holds NFTs from two collections, raffle 2 from one of them
+    let params = to_json_binary(&raffle_params(now + 300, None)).unwrap();
+    execute(deps.as_mut(), env_at(now), mock_info("apes", &[]), receive_nft("1", params.clone())).unwrap();
+    mock_nft_owner(&mut deps);
+    let add = ExecuteMsg::AddNftPrize { raffle_id: 1, nft_contract: "punks".into(), token_id: "7".into() };
+    execute(deps.as_mut(), env_at(now), mock_info("creator", &[]), add).unwrap();
+    let params = to_json_binary(&raffle_params(now + 100, None)).unwrap();
+    execute(deps.as_mut(), env_at(now), mock_info("punks", &[]), receive_nft("8", params)).unwrap();
+    let coin_raffle = create_coin_raffle(&mut deps, now, raffle_params(now + 200, None));
+
+    let by_collection = |deps: &TestDeps, nft_contract: &str| {
+        let msg = QueryMsg::RafflesByCollection { nft_contract: nft_contract.into(), start_after: None, limit: None };
+        from_json::<RaffleListResponse>(query(deps.as_ref(), env_at(now), msg).unwrap()).unwrap()
+    };
+    assert_eq!(ids_of(by_collection(&deps, "apes")), vec![1]);
+    assert_eq!(ids_of(by_collection(&deps, "punks")), vec![1, 2]);
+
+    // Cancelling raffle 2 moves it out of the active (status, end_time) range
+    execute(deps.as_mut(), env_at(now), mock_info("creator", &[]), ExecuteMsg::CancelRaffle { raffle_id: 2 }).unwrap();
+    let ending = |deps: &TestDeps, status: Option<RaffleStatus>, start_after: Option<u64>, limit: u32| {
+        let msg = QueryMsg::RafflesEndingBefore {
+            time: Timestamp::from_seconds(now + 1000),
+            status,
+            start_after: start_after.map(|id| id.to_string()),
+            limit: Some(limit),
+        };
+        from_json::<RaffleListResponse>(query(deps.as_ref(), env_at(now), msg).unwrap()).unwrap()
+    };
+    assert_eq!(ids_of(ending(&deps, None, None, 10)), vec![2, coin_raffle, 1]);
+    assert_eq!(ids_of(ending(&deps, Some(RaffleStatus::Active), None, 1)), vec![coin_raffle]);
+    assert_eq!(ids_of(ending(&deps, Some(RaffleStatus::Active), Some(coin_raffle), 10)), vec![1]);
+    assert_eq!(ids_of(ending(&deps, Some(RaffleStatus::Cancelled), None, 10)), vec![2]);
+}
+
+#[test]
+fn prizes_are_added_only_by_the_creator_and_cannot_block_settlement() {
+    let mut deps = setup();
+    let now = 1_700_000_000;
+    let raffle_id = create_coin_raffle(&mut deps, now, raffle_params(now + 300, None));
+
+    // A cw721 contract can claim any sender in its hook, so the hook only creates raffles
+    let forged = ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
+        sender: "creator".into(),
+        token_id: "1".into(),
+        msg: to_json_binary(&serde_json::json!({ "raffle_id": raffle_id })).unwrap(),
+    });
+    let err = execute(deps.as_mut(), env_at(now), mock_info("fake-nft", &[]), forged).unwrap_err();
+    assert_eq!(err, ContractError::InvalidHookMsg {});
+
+    // The creator's own AddNftPrize pulls the approved token
+    mock_nft_owner(&mut deps);
+    let add = |deps: &mut TestDeps, sender: &str| execute(
+        deps.as_mut(), env_at(now), mock_info(sender, &[]),
+        ExecuteMsg::AddNftPrize { raffle_id, nft_contract: "collection".into(), token_id: "2".into() },
+    );
+    assert_eq!(add(&mut deps, "mallory").unwrap_err(), ContractError::NotNftOwner {});
+    let res = add(&mut deps, "creator").unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    // Same for cw20 prizes, which are pulled through an allowance. The old add-prize
+    // payload now reads as a purchase, which the raffle's payment denom rejects.
+    let forged = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+        sender: "creator".into(),
+        amount: Uint128::new(1000),
+        msg: to_json_binary(&serde_json::json!({ "raffle_id": raffle_id })).unwrap(),
+    });
+    let err = execute(deps.as_mut(), env_at(now), mock_info("fake-token", &[]), forged).unwrap_err();
+    assert_eq!(err, ContractError::ExpectsNativePayment {});
+    let add = ExecuteMsg::AddCw20Prize { raffle_id, token: "token".into(), amount: Uint128::new(1000) };
+    let res = execute(deps.as_mut(), env_at(now), mock_info("creator", &[]), add).unwrap();
+    let pull = Cw20ExecuteMsg::TransferFrom { owner: "creator".into(), recipient: MOCK_CONTRACT_ADDR.into(), amount: Uint128::new(1000) };
+    assert_eq!(res.messages[0].msg, WasmMsg::Execute { contract_addr: "token".into(), msg: to_json_binary(&pull).unwrap(), funds: vec![] }.into());
+
+    // Returned prizes are submessages whose failure is caught by reply
+    let res = execute(deps.as_mut(), env_at(now), mock_info("creator", &[]), ExecuteMsg::CancelRaffle { raffle_id }).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert!(res.messages.iter().all(|m| m.reply_on == ReplyOn::Error && m.id == PRIZE_TRANSFER_REPLY_ID));
+    let failed = Reply { id: PRIZE_TRANSFER_REPLY_ID, result: SubMsgResult::Err("transfer refused".into()) };
+    let res = reply(deps.as_mut(), env_at(now), failed).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("action", "prize_transfer_failed")));
+}
+
+#[test]
+fn fee_is_capped_and_fixed_at_creation() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: Some("admin".into()),
+        protocol_fee_bps: 10_001,
+        bounty_amount: None,
+        drand_chains: vec![mainnet()],
+        default_drand_chain: Some(MAINNET_HASH.into()),
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeBps { fee_bps: 10_001 });
+
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let raffle_id = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+    let update = |fee_bps| ExecuteMsg::UpdateConfig { fee_bps: Some(fee_bps), bounty: None, default_drand_chain: None };
+    let err = execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), update(10_001)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeBps { fee_bps: 10_001 });
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), update(5_000)).unwrap();
+
+    let view = |deps: &TestDeps, raffle_id| {
+        let res: RaffleResponse = from_json(query(deps.as_ref(), env_at(now), QueryMsg::Raffle { raffle_id }).unwrap()).unwrap();
+        res.raffle.unwrap().protocol_fee_bps
+    };
+    let later = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+    assert_eq!(view(&deps, raffle_id), 0);
+    assert_eq!(view(&deps, later), 5_000);
+
+    // The raffle created at 0 bps still settles without a fee
+    buy(&mut deps, now, raffle_id, "alice", 2).unwrap();
+    let res = end_mainnet(&mut deps, raffle_id).unwrap();
+    assert!(res.attributes.iter().all(|a| a.key != "protocol_fee"));
+}
+
+#[test]
+fn bounty_is_fixed_at_creation_and_capped() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let before = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+    let bounty = ExecuteMsg::UpdateConfig { fee_bps: None, bounty: Some(Coin::new(1_000_000, "ucore")), default_drand_chain: None };
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), bounty).unwrap();
+    let after = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+
+    let view = |deps: &TestDeps, raffle_id| {
+        let res: RaffleResponse = from_json(query(deps.as_ref(), env_at(now), QueryMsg::Raffle { raffle_id }).unwrap()).unwrap();
+        res.raffle.unwrap().bounty
+    };
+    assert_eq!(view(&deps, before), None);
+    assert_eq!(view(&deps, after), Some(Coin::new(1_000_000, "ucore")));
+
+    // However large the bounty, the executor gets at most 5% of the 200 in sales
+    buy(&mut deps, now, after, "alice", 2).unwrap();
+    let res = end_mainnet(&mut deps, after).unwrap();
+    assert!(res.attributes.contains(&Attribute::new("bounty_paid", "10")));
+}
+
+#[test]
+fn renouncing_admin_requires_withdrawn_fees() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let fee = ExecuteMsg::UpdateConfig { fee_bps: Some(1_000), bounty: None, default_drand_chain: None };
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), fee).unwrap();
+    let raffle_id = create_coin_raffle(&mut deps, now, raffle_params(mainnet_end(), None));
+    buy(&mut deps, now, raffle_id, "alice", 2).unwrap();
+    end_mainnet(&mut deps, raffle_id).unwrap();
+
+    // Only the admin can withdraw, so the 20 in fees must go first
+    let renounce = |deps: &mut TestDeps| execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), ExecuteMsg::RenounceAdmin {});
+    assert_eq!(renounce(&mut deps).unwrap_err(), ContractError::FeesNotWithdrawn { denom: "ucore".into() });
+    let withdraw = ExecuteMsg::WithdrawFees { to: "treasury".into(), denoms: vec!["ucore".into()], amounts: vec![Uint128::new(20)] };
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), withdraw).unwrap();
+    renounce(&mut deps).unwrap();
+}
+
+#[test]
+fn drand_chain_hashes_match_in_any_case() {
+    let mut deps = setup();
+    let now = mainnet_end() - 1000;
+    let upper = MAINNET_HASH.to_uppercase();
+    let mut params = raffle_params(mainnet_end(), None);
+    params.drand_chain = Some(upper.clone());
+    let raffle_id = create_coin_raffle(&mut deps, now, params);
+    assert_eq!(RAFFLES.load(&deps.storage, raffle_id).unwrap().drand_chain, MAINNET_HASH);
+
+    let add = ExecuteMsg::AddDrandChain { chain: quicknet() };
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), add).unwrap();
+    let update = ExecuteMsg::UpdateConfig { fee_bps: None, bounty: None, default_drand_chain: Some(QUICKNET_HASH.to_uppercase()) };
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), update).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().default_drand_chain.as_deref(), Some(QUICKNET_HASH));
+
+    // The default chain can't be switched off under another casing either
+    let deactivate = |chain_hash: String| ExecuteMsg::SetDrandChainActive { chain_hash, active: false };
+    let err = execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), deactivate(QUICKNET_HASH.to_uppercase())).unwrap_err();
+    assert_eq!(err, ContractError::DeactivateDefaultDrandChain {});
+    execute(deps.as_mut(), env_at(now), mock_info("admin", &[]), deactivate(upper)).unwrap();
+    assert!(!DRAND_CHAINS.load(&deps.storage, MAINNET_HASH).unwrap().active);
+}
+
+#[test]
+fn storage_and_overflow_errors_carry_codes() {
+    let mut deps = setup();
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::ExpireRaffle { raffle_id: 42 }).unwrap_err();
+    assert!(err.to_string().starts_with("E001: "), "{err}");
+    let err = ContractError::from(Uint128::MAX.checked_add(Uint128::one()).unwrap_err());
+    assert!(err.to_string().starts_with("E002: "), "{err}");
+}
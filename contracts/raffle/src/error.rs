@@ -0,0 +1,216 @@
+use cosmwasm_std::{OverflowError, StdError, Uint128};
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+// Every message starts with a stable code so clients can match on it:
+// E0xx storage/arithmetic, E1xx admin/config, E2xx raffle lifecycle,
+// E3xx tickets/payment, E4xx drand, E5xx refunds/fees, E6xx NFT escrow.
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("E001: {0}")]
+    Std(#[from] StdError),
+
+    #[error("E002: {0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("E301: {0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("E101: unauthorized")]
+    Unauthorized {},
+
+    #[error("E102: invalid hook msg")]
+    InvalidHookMsg {},
+
+    #[error("E103: no drand chain configured")]
+    NoDrandChainConfigured {},
+
+    #[error("E104: unknown drand chain {chain_hash}")]
+    UnknownDrandChain { chain_hash: String },
+
+    #[error("E105: drand chain {chain_hash} is inactive")]
+    DrandChainInactive { chain_hash: String },
+
+    #[error("E106: drand chain {chain_hash} already registered")]
+    DrandChainExists { chain_hash: String },
+
+    #[error("E107: cannot deactivate the default drand chain")]
+    DeactivateDefaultDrandChain {},
+
+    #[error("E108: invalid drand chain hash - must be 32 bytes hex")]
+    InvalidDrandChainHash {},
+
+    #[error("E109: drand period must be > 0")]
+    ZeroDrandPeriod {},
+
+    #[error("E110: cannot migrate from contract {contract}")]
+    WrongContract { contract: String },
+
+    #[error("E111: cannot downgrade from {stored} to {current}")]
+    CannotDowngrade { stored: String, current: String },
+
+    #[error("E112: invalid contract version {version}")]
+    InvalidVersion { version: String },
+
+    #[error("E113: contract is paused")]
+    Paused {},
+
+    #[error("E114: no admin transfer pending")]
+    NoPendingAdmin {},
+
+    #[error("E115: no reindex pending")]
+    NoReindexPending {},
+
+    #[error("E116: fee_bps {fee_bps} exceeds 10000")]
+    InvalidFeeBps { fee_bps: u16 },
+
+    #[error("E117: withdraw the collected {denom} fees before renouncing admin")]
+    FeesNotWithdrawn { denom: String },
+
+    #[error("E201: end_time must be in the future")]
+    EndTimeNotInFuture {},
+
+    #[error("E202: start_time < end_time required")]
+    InvalidStartTime {},
+
+    #[error("E203: ticket price must be > 0")]
+    ZeroTicketPrice {},
+
+    #[error("E204: raffle {raffle_id} not active")]
+    RaffleNotActive { raffle_id: u64 },
+
+    #[error("E205: raffle {raffle_id} not started")]
+    RaffleNotStarted { raffle_id: u64 },
+
+    #[error("E206: raffle {raffle_id} ended")]
+    RaffleEnded { raffle_id: u64 },
+
+    #[error("E207: raffle {raffle_id} not ready to end")]
+    RaffleNotReadyToEnd { raffle_id: u64 },
+
+    #[error("E208: raffle {raffle_id} has no tickets sold, use ExpireRaffle")]
+    NoTicketsSold { raffle_id: u64 },
+
+    #[error("E209: raffle {raffle_id} has tickets sold, use EndRaffle")]
+    TicketsSold { raffle_id: u64 },
+
+    #[error("E211: cannot cancel after start")]
+    CancelAfterStart {},
+
+    #[error("E212: min_tickets {min_tickets} exceeds max_tickets {max_tickets}")]
+    InvalidMinTickets { min_tickets: u64, max_tickets: u64 },
+
+    #[error("E213: ticket caps must be > 0")]
+    ZeroTicketCap {},
+
+    #[error("E214: lottery tiers must be non-zero bps summing to 10000, at most {max}")]
+    InvalidPotTiers { max: usize },
+
+    #[error("E216: raffle {raffle_id} tickets await migration, run Reindex first")]
+    TicketsNotMigrated { raffle_id: u64 },
+
+    #[error("E302: count must be > 0")]
+    ZeroTicketCount {},
+
+    #[error("E303: exceeds max tickets: requested {requested}, remaining {remaining}")]
+    ExceedsMaxTickets { requested: u64, remaining: u64 },
+
+    #[error("E309: exceeds per-transaction cap: requested {requested}, max {max}")]
+    ExceedsTxCap { requested: u64, max: u64 },
+
+    #[error("E310: exceeds per-wallet cap: requested {requested}, remaining {remaining}")]
+    ExceedsWalletCap { requested: u64, remaining: u64 },
+
+    #[error("E311: the creator and revenue address can't buy into commit-reveal raffle {raffle_id}")]
+    CommitterCannotBuy { raffle_id: u64 },
+
+    #[error("E304: raffle expects native payment")]
+    ExpectsNativePayment {},
+
+    #[error("E305: raffle expects cw20 payment")]
+    ExpectsCw20Payment {},
+
+    #[error("E306: wrong payment token: expected {expected}, got {got}")]
+    WrongPaymentToken { expected: String, got: String },
+
+    #[error("E308: insufficient payment: required {required}, paid {paid}")]
+    InsufficientPayment { required: Uint128, paid: Uint128 },
+
+    #[error("E401: drand round mismatch: expected {expected}, got {got}")]
+    DrandRoundMismatch { expected: u64, got: u64 },
+
+    #[error("E402: drand round {round} already used")]
+    DrandRoundUsed { round: u64 },
+
+    #[error("E403: invalid drand input")]
+    InvalidDrandInput {},
+
+    #[error("E404: invalid drand signature")]
+    InvalidDrandSignature {},
+
+    #[error("E405: randomness does not match signature")]
+    RandomnessMismatch {},
+
+    #[error("E406: previous_signature required for chained drand beacon")]
+    MissingPreviousSignature {},
+
+    #[error("E407: invalid {what} hex")]
+    InvalidHex { what: String },
+
+    #[error("E408: invalid {what} length - must be {expected} bytes")]
+    InvalidLength { what: String, expected: usize },
+
+    #[error("E409: invalid {what} format")]
+    InvalidPoint { what: String },
+
+    #[error("E410: raffle {raffle_id} uses a different randomness source")]
+    WrongRandomnessSource { raffle_id: u64 },
+
+    #[error("E411: commitment must be a 32 byte sha256 hex digest")]
+    InvalidCommitment {},
+
+    #[error("E412: revealed secret does not match the commitment")]
+    RevealMismatch {},
+
+    #[error("E413: reveal deadline of raffle {raffle_id} has passed")]
+    RevealDeadlinePassed { raffle_id: u64 },
+
+    #[error("E414: reveal timeout must be > 0")]
+    ZeroRevealTimeout {},
+
+    #[error("E501: raffle {raffle_id} not refundable")]
+    NotRefundable { raffle_id: u64 },
+
+    #[error("E502: refund already claimed")]
+    RefundAlreadyClaimed {},
+
+    #[error("E503: no tickets to refund")]
+    NoTicketsToRefund {},
+
+    #[error("E504: denoms and amounts must be non-empty and of equal length")]
+    InvalidWithdrawal {},
+
+    #[error("E505: withdraw amount must be > 0")]
+    ZeroWithdrawAmount {},
+
+    #[error("E506: insufficient fees in {denom}: requested {requested}, available {available}")]
+    InsufficientFees { denom: String, requested: Uint128, available: Uint128 },
+
+    #[error("E601: sender does not own the NFT")]
+    NotNftOwner {},
+
+    #[error("E602: contract not approved to transfer the NFT")]
+    NftNotApproved {},
+
+    #[error("E603: raffle {raffle_id} no longer accepts prizes")]
+    PrizesLocked { raffle_id: u64 },
+
+    #[error("E604: too many prizes - max {max}")]
+    TooManyPrizes { max: usize },
+
+    #[error("E605: prize must carry a non-zero amount")]
+    EmptyPrize {},
+
+    #[error("E606: raffle {raffle_id} prizes are not held in escrow")]
+    PrizesNotEscrowed { raffle_id: u64 },
+}
@@ -1,6 +1,9 @@
 pub mod msg;
 pub mod state;
 pub mod contract;
+pub mod error;
+mod migrations;
 
-pub use crate::contract::{execute, instantiate, query};
+pub use crate::contract::{execute, instantiate, migrate, query, reply, winning_ticket};
+pub use crate::error::ContractError;
 
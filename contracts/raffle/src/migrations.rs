@@ -0,0 +1,551 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, DepsMut, Env, Order, StdResult, Storage, Timestamp};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::contract::{drand_round_after, transfer_nft_msg, validate_drand_chain};
+use crate::error::ContractError;
+use crate::msg::{DrandChainMsg, MigrateMsg};
+use crate::state::{Config, DrandScheme, EscrowStatus, Prize, PrizeWinner, Raffle, RaffleStatus, RandomnessSource, TicketRange, CONFIG, DRAND_CHAINS, RAFFLES, TICKET_RANGES, OWNER_TICKET_RANGES, REINDEX, Reindex, USER_RAFFLES, USER_TICKET_COUNT, USER_WINS};
+
+// Migrations run in order for every version step the stored contract is behind.
+// Each step reads the previous layout from the same storage keys and rewrites it.
+
+// League of Entropy mainnet, the only beacon accepted before the chain registry
+const LOE_MAINNET_CHAIN_HASH: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+const LOE_MAINNET_PUBKEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
+const LOE_MAINNET_GENESIS_TIME: u64 = 1595431050;
+const LOE_MAINNET_PERIOD: u64 = 30;
+
+mod v0_1 {
+    use super::*;
+
+    #[cw_serde]
+    pub struct Config {
+        pub admin: Addr,
+        pub protocol_fee_bps: u16,
+        pub bounty_amount: Option<Coin>,
+        pub drand_pubkey: Option<String>,
+        pub drand_round_seconds: Option<u64>,
+    }
+
+    #[cw_serde]
+    pub struct Raffle {
+        pub id: u64,
+        pub creator: Addr,
+        pub nft_contract: Addr,
+        pub token_id: String,
+        pub price: Coin,
+        pub max_tickets: u64,
+        pub total_sold: u64,
+        pub start_time: Option<Timestamp>,
+        pub end_time: Timestamp,
+        pub revenue_address: Addr,
+        pub payment_cw20: Option<Addr>,
+        pub status: RaffleStatus,
+        pub winner: Option<Addr>,
+    }
+
+    pub const CONFIG: Item<Config> = Item::new("config");
+    pub const RAFFLES: Map<u64, Raffle> = Map::new("raffles");
+}
+
+mod v0_2 {
+    use super::*;
+
+    // Ticket index -> owner, one entry per ticket
+    pub const TICKETS: Map<(u64, u64), Addr> = Map::new("tickets");
+}
+
+mod v0_3 {
+    use super::*;
+
+    // Single NFT prize and winner per raffle
+    #[cw_serde]
+    pub struct Raffle {
+        pub id: u64,
+        pub creator: Addr,
+        pub nft_contract: Addr,
+        pub token_id: String,
+        pub nft_escrow: EscrowStatus,
+        pub price: Coin,
+        pub max_tickets: u64,
+        pub min_tickets: Option<u64>,
+        pub max_tickets_per_wallet: Option<u64>,
+        pub max_tickets_per_tx: Option<u64>,
+        pub total_sold: u64,
+        pub start_time: Option<Timestamp>,
+        pub end_time: Timestamp,
+        pub revenue_address: Addr,
+        pub payment_cw20: Option<Addr>,
+        pub drand_chain: String,
+        pub drand_round: Option<u64>,
+        pub status: RaffleStatus,
+        pub winner: Option<Addr>,
+    }
+
+    pub const RAFFLES: Map<u64, Raffle> = Map::new("raffles");
+}
+
+/// 0.1.x -> 0.2.0: drand chain registry, NFT escrow tracking and pinned rounds.
+/// Returns the transfers handing back NFTs that 0.1 kept after a raffle was cancelled.
+pub fn v0_2_0(deps: DepsMut, env: &Env, msg: &MigrateMsg) -> Result<Vec<CosmosMsg>, ContractError> {
+    // Register the chain the old single drand_pubkey pointed at
+    let chain = validate_drand_chain(msg.legacy_drand_chain.clone().unwrap_or(DrandChainMsg {
+        chain_hash: LOE_MAINNET_CHAIN_HASH.to_string(),
+        pubkey: LOE_MAINNET_PUBKEY.to_string(),
+        genesis_time: LOE_MAINNET_GENESIS_TIME,
+        period: LOE_MAINNET_PERIOD,
+        scheme: DrandScheme::Chained,
+    }))?;
+    if !DRAND_CHAINS.has(deps.storage, &chain.chain_hash) {
+        DRAND_CHAINS.save(deps.storage, &chain.chain_hash, &chain)?;
+    }
+
+    let old_cfg = v0_1::CONFIG.load(deps.storage)?;
+    CONFIG.save(deps.storage, &Config {
+        admin: Some(old_cfg.admin),
+        pending_admin: None,
+        protocol_fee_bps: old_cfg.protocol_fee_bps,
+        bounty_amount: old_cfg.bounty_amount,
+        default_drand_chain: Some(chain.chain_hash.clone()),
+        paused: false,
+    })?;
+
+    let old_raffles: StdResult<Vec<_>> = v0_1::RAFFLES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    let mut returns = vec![];
+    for (id, r) in old_raffles? {
+        // Raffles that already sold out close at the upgrade, the old code never pinned a round
+        let drand_round = (matches!(r.status, RaffleStatus::Active) && r.total_sold >= r.max_tickets)
+            .then(|| drand_round_after(&chain, env.block.time));
+        let mut price = r.price;
+        if let Some(token) = &r.payment_cw20 {
+            price.denom = token.to_string();
+        }
+        // 0.1 direct CreateRaffle never took the NFT, so only trust the cw721 owner
+        let nft_escrow = match r.status {
+            RaffleStatus::Completed => EscrowStatus::Released,
+            _ => {
+                let owner: StdResult<cw721::OwnerOfResponse> = deps.querier.query_wasm_smart(
+                    &r.nft_contract,
+                    &cw721::Cw721QueryMsg::OwnerOf { token_id: r.token_id.clone(), include_expired: Some(false) },
+                );
+                match owner {
+                    // A finished raffle that didn't award the NFT owes it to the creator
+                    Ok(owner) if owner.owner == env.contract.address.as_str() && !matches!(r.status, RaffleStatus::Active) => {
+                        returns.push(transfer_nft_msg(&r.nft_contract, &r.creator, &r.token_id)?.into());
+                        EscrowStatus::Released
+                    }
+                    Ok(owner) if owner.owner == env.contract.address.as_str() => EscrowStatus::Held,
+                    _ => EscrowStatus::NotEscrowed,
+                }
+            }
+        };
+        let raffle = v0_3::Raffle {
+            id: r.id,
+            creator: r.creator,
+            nft_contract: r.nft_contract,
+            token_id: r.token_id,
+            nft_escrow,
+            price,
+            max_tickets: r.max_tickets,
+            min_tickets: None,
+            max_tickets_per_wallet: None,
+            max_tickets_per_tx: None,
+            total_sold: r.total_sold,
+            start_time: r.start_time,
+            end_time: r.end_time,
+            revenue_address: r.revenue_address,
+            payment_cw20: r.payment_cw20,
+            drand_chain: chain.chain_hash.clone(),
+            drand_round,
+            status: r.status,
+            winner: r.winner,
+        };
+        v0_3::RAFFLES.save(deps.storage, id, &raffle)?;
+    }
+    Ok(returns)
+}
+
+/// 0.2.x -> 0.3.0: per-ticket TICKETS entries collapse into TICKET_RANGES. That is
+/// one write per ticket ever sold, so it runs in `Reindex` batches; raffles still
+/// holding TICKETS can't be drawn until then.
+pub fn v0_3_0(deps: DepsMut) -> Result<(), ContractError> {
+    REINDEX.save(deps.storage, &Reindex::default())?;
+    Ok(())
+}
+
+/// Whether `raffle_id` still has per-ticket entries waiting for `Reindex`
+pub fn tickets_pending(storage: &dyn Storage, raffle_id: u64) -> StdResult<bool> {
+    Ok(v0_2::TICKETS.prefix(raffle_id).keys(storage, None, None, Order::Ascending).next().transpose()?.is_some())
+}
+
+/// 0.3.x -> 0.4.0: the single NFT and winner become the first entries of the prize
+/// and winner lists.
+pub fn v0_4_0(deps: DepsMut) -> Result<(), ContractError> {
+    let old_raffles: StdResult<Vec<_>> = v0_3::RAFFLES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    for (id, r) in old_raffles? {
+        let raffle = Raffle {
+            id: r.id,
+            creator: r.creator,
+            prizes: vec![Prize::Nft { contract: r.nft_contract, token_id: r.token_id }],
+            nft_escrow: r.nft_escrow,
+            price: r.price,
+            max_tickets: r.max_tickets,
+            min_tickets: r.min_tickets,
+            max_tickets_per_wallet: r.max_tickets_per_wallet,
+            max_tickets_per_tx: r.max_tickets_per_tx,
+            total_sold: r.total_sold,
+            start_time: r.start_time,
+            end_time: r.end_time,
+            revenue_address: r.revenue_address,
+            payment_cw20: r.payment_cw20,
+            randomness_source: RandomnessSource::Drand,
+            drand_chain: r.drand_chain,
+            drand_round: r.drand_round,
+            status: r.status,
+            // The winning ticket index was never stored
+            winners: r.winner.into_iter().map(|winner| PrizeWinner { prize: 0, winner, ticket_index: None }).collect(),
+            pot: None,
+            protocol_fee_bps: None,
+            bounty: None,
+        };
+        // The stored value is still a 0.3 raffle, so don't let the index diff load it
+        RAFFLES.replace(deps.storage, id, Some(&raffle), None)?;
+    }
+    Ok(())
+}
+
+/// 0.4.x -> 0.5.0: schedule the backfill of the creator, status, collection and end
+/// time indexes of existing raffles. The work is O(raffles + purchases), so it runs in
+/// `Reindex` batches instead of inside the migration.
+pub fn v0_5_0(deps: DepsMut) -> Result<(), ContractError> {
+    REINDEX.save(deps.storage, &Reindex::default())?;
+    Ok(())
+}
+
+/// 0.5.x -> 0.6.0: 0.5.0 didn't track entries and wins per user, ticket ranges per
+/// owner, every prize collection or (status, end_time), so (re)start the backfill,
+/// which also covers those. The 0.5 first-collection index is no longer read. Coming
+/// from 0.4 this just restarts the pass scheduled by v0_5_0.
+pub fn v0_6_0(deps: DepsMut) -> Result<(), ContractError> {
+    REINDEX.save(deps.storage, &Reindex::default())?;
+    Ok(())
+}
+
+/// One `Reindex` batch of up to `limit` entries: pre-0.3 tickets first, then raffles,
+/// then ticket ranges, then buyers' ticket counts, moving on to the next phase when one runs out. Returns the
+/// entries processed and whether the backfill is complete. Re-indexing an entry twice
+/// is harmless, every write is idempotent.
+pub fn reindex(deps: DepsMut, limit: u32) -> Result<(u32, bool), ContractError> {
+    let mut progress = REINDEX.may_load(deps.storage)?.ok_or(ContractError::NoReindexPending {})?;
+    let mut processed = 0;
+    while processed < limit {
+        let budget = (limit - processed) as usize;
+        if !progress.tickets_done {
+            // Converted tickets are removed, so each batch starts from the first one left
+            let tickets: StdResult<Vec<_>> = v0_2::TICKETS
+                .range(deps.storage, None, None, Order::Ascending)
+                .take(budget)
+                .collect();
+            let tickets = tickets?;
+            progress.tickets_done = tickets.len() < budget;
+            for ((raffle_id, index), owner) in tickets {
+                // Keys are ordered by index, so a ticket extends its owner's run ending just before it
+                let prev = TICKET_RANGES
+                    .prefix(raffle_id)
+                    .range(deps.storage, None, Some(Bound::exclusive(index)), Order::Descending)
+                    .next()
+                    .transpose()?;
+                match prev {
+                    Some((start, mut range)) if start + range.count == index && range.owner == owner => {
+                        range.count += 1;
+                        TICKET_RANGES.save(deps.storage, (raffle_id, start), &range)?;
+                    }
+                    _ => TICKET_RANGES.save(deps.storage, (raffle_id, index), &TicketRange { owner, count: 1 })?,
+                }
+                v0_2::TICKETS.remove(deps.storage, (raffle_id, index));
+                processed += 1;
+            }
+        } else if !progress.raffles_done {
+            let raffles: StdResult<Vec<_>> = RAFFLES
+                .range(deps.storage, progress.raffle_after.map(Bound::exclusive), None, Order::Ascending)
+                .take(budget)
+                .collect();
+            let raffles = raffles?;
+            progress.raffles_done = raffles.len() < budget;
+            let cfg = CONFIG.load(deps.storage)?;
+            for (id, mut raffle) in raffles {
+                // Raffles from before 0.6.0 keep the fee and bounty in effect now
+                if raffle.protocol_fee_bps.is_none() {
+                    raffle.protocol_fee_bps = Some(cfg.protocol_fee_bps);
+                    raffle.bounty = cfg.bounty_amount.clone();
+                }
+                // Adds any missing index entries; existing ones are rewritten unchanged
+                RAFFLES.replace(deps.storage, id, Some(&raffle), None)?;
+                for w in &raffle.winners {
+                    USER_WINS.save(deps.storage, (&w.winner, id), &w.prize)?;
+                }
+                progress.raffle_after = Some(id);
+                processed += 1;
+            }
+        } else if !progress.ranges_done {
+            let ranges: StdResult<Vec<_>> = TICKET_RANGES
+                .range(deps.storage, progress.range_after.map(Bound::exclusive), None, Order::Ascending)
+                .take(budget)
+                .collect();
+            let ranges = ranges?;
+            progress.ranges_done = ranges.len() < budget;
+            for ((raffle_id, start), range) in ranges {
+                OWNER_TICKET_RANGES.save(deps.storage, (raffle_id, &range.owner, start), &range.count)?;
+                progress.range_after = Some((raffle_id, start));
+                processed += 1;
+            }
+        } else {
+            // Every buyer of every raffle has a count, whatever became of their tickets
+            let entrants: StdResult<Vec<_>> = USER_TICKET_COUNT
+                .keys(deps.storage, progress.entrant_after.as_ref().map(|(id, addr)| Bound::exclusive((*id, addr))), None, Order::Ascending)
+                .take(budget)
+                .collect();
+            let entrants = entrants?;
+            let done = entrants.len() < budget;
+            for (raffle_id, buyer) in entrants {
+                USER_RAFFLES.save(deps.storage, (&buyer, raffle_id), &true)?;
+                progress.entrant_after = Some((raffle_id, buyer));
+                processed += 1;
+            }
+            if done {
+                REINDEX.remove(deps.storage);
+                return Ok((processed, true));
+            }
+        }
+    }
+    REINDEX.save(deps.storage, &progress)?;
+    Ok((processed, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_json, to_json_binary, ContractResult, SystemResult, WasmMsg, WasmQuery};
+    use crate::contract::{execute, migrate};
+    use crate::msg::ExecuteMsg;
+
+    fn v0_1_raffle(id: u64, token_id: &str, status: RaffleStatus, end: Timestamp) -> v0_1::Raffle {
+        v0_1::Raffle {
+            id,
+            creator: Addr::unchecked("creator"),
+            nft_contract: Addr::unchecked("collection"),
+            token_id: token_id.into(),
+            price: Coin::new(100, "ucore"),
+            max_tickets: 10,
+            total_sold: 0,
+            start_time: None,
+            end_time: end,
+            revenue_address: Addr::unchecked("creator"),
+            payment_cw20: None,
+            status,
+            winner: None,
+        }
+    }
+
+    #[test]
+    fn v0_1_raffles_keep_track_of_unescrowed_nfts() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let contract = env.contract.address.to_string();
+        deps.querier.update_wasm(move |q| match q {
+            WasmQuery::Smart { msg, .. } => {
+                let cw721::Cw721QueryMsg::OwnerOf { token_id, .. } = from_json(msg).unwrap() else { panic!("unexpected query") };
+                let owner = if token_id == "deposited" { contract.clone() } else { "creator".to_string() };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&cw721::OwnerOfResponse { owner, approvals: vec![] }).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        cw2::set_contract_version(&mut deps.storage, "coreum-raffle", "0.1.0").unwrap();
+        v0_1::CONFIG.save(&mut deps.storage, &v0_1::Config {
+            admin: Addr::unchecked("admin"),
+            protocol_fee_bps: 0,
+            bounty_amount: None,
+            drand_pubkey: None,
+            drand_round_seconds: None,
+        }).unwrap();
+        let open = env.block.time.plus_seconds(1000);
+        let closed = env.block.time.minus_seconds(1000);
+        v0_1::RAFFLES.save(&mut deps.storage, 1, &v0_1_raffle(1, "deposited", RaffleStatus::Active, open)).unwrap();
+        v0_1::RAFFLES.save(&mut deps.storage, 2, &v0_1_raffle(2, "approved", RaffleStatus::Active, closed)).unwrap();
+        v0_1::RAFFLES.save(&mut deps.storage, 3, &v0_1_raffle(3, "won", RaffleStatus::Completed, closed)).unwrap();
+        let mut sold = v0_1_raffle(4, "deposited", RaffleStatus::Active, open);
+        sold.total_sold = 3;
+        v0_1::RAFFLES.save(&mut deps.storage, 4, &sold).unwrap();
+        v0_1::RAFFLES.save(&mut deps.storage, 5, &v0_1_raffle(5, "deposited", RaffleStatus::Cancelled, closed)).unwrap();
+        for (raffle_id, index, owner) in [(3, 0, "alice"), (4, 0, "alice"), (4, 1, "alice"), (4, 2, "bob")] {
+            v0_2::TICKETS.save(&mut deps.storage, (raffle_id, index), &Addr::unchecked(owner)).unwrap();
+        }
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { legacy_drand_chain: None }).unwrap();
+        let escrow = |id| RAFFLES.load(&deps.storage, id).unwrap().nft_escrow;
+        assert_eq!(escrow(1), EscrowStatus::Held);
+        assert_eq!(escrow(2), EscrowStatus::NotEscrowed);
+        assert_eq!(escrow(3), EscrowStatus::Released);
+        // The NFT 0.1 kept after cancelling raffle 5 goes back with the migration
+        assert_eq!(escrow(5), EscrowStatus::Released);
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, transfer_nft_msg(&Addr::unchecked("collection"), &Addr::unchecked("creator"), "deposited").unwrap().into());
+
+        // Nothing can be added to a raffle whose NFT was never escrowed
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &coins(10, "ucore")), ExecuteMsg::AddCoinPrize { raffle_id: 2 }).unwrap_err();
+        assert_eq!(err, ContractError::PrizesNotEscrowed { raffle_id: 2 });
+
+        // The deposited NFT goes back on cancel, the creator's own one isn't touched
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), ExecuteMsg::CancelRaffle { raffle_id: 1 }).unwrap();
+        assert!(matches!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute { ref contract_addr, .. }) if contract_addr == "collection"));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), ExecuteMsg::ExpireRaffle { raffle_id: 2 }).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(matches!(RAFFLES.load(&deps.storage, 2).unwrap().status, RaffleStatus::Expired));
+
+        // Tickets wait for Reindex, and the raffle holding them can't be drawn before
+        assert!(!has_ticket_ranges(&deps, 4));
+        let mut raffle = RAFFLES.load(&deps.storage, 4).unwrap();
+        raffle.end_time = closed;
+        RAFFLES.save(&mut deps.storage, 4, &raffle).unwrap();
+        let end = ExecuteMsg::EndRaffle { raffle_id: 4, drand_round: 1, randomness: "00".into(), signature: "00".into(), previous_signature: None };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), end).unwrap_err();
+        assert_eq!(err, ContractError::TicketsNotMigrated { raffle_id: 4 });
+
+        // Two tickets per batch: the run of alice's tickets spans a batch boundary
+        while !execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), ExecuteMsg::Reindex { limit: Some(2) })
+            .unwrap()
+            .attributes
+            .contains(&cosmwasm_std::Attribute::new("done", "true")) {}
+        assert_eq!(TICKET_RANGES.load(&deps.storage, (3, 0)).unwrap(), TicketRange { owner: Addr::unchecked("alice"), count: 1 });
+        assert_eq!(TICKET_RANGES.load(&deps.storage, (4, 0)).unwrap(), TicketRange { owner: Addr::unchecked("alice"), count: 2 });
+        assert_eq!(TICKET_RANGES.load(&deps.storage, (4, 2)).unwrap(), TicketRange { owner: Addr::unchecked("bob"), count: 1 });
+        assert!(!has_ticket_ranges(&deps, 1));
+        assert!(!tickets_pending(&deps.storage, 3).unwrap());
+        assert!(!tickets_pending(&deps.storage, 4).unwrap());
+    }
+
+    fn has_ticket_ranges(deps: &cosmwasm_std::OwnedDeps<cosmwasm_std::MemoryStorage, cosmwasm_std::testing::MockApi, cosmwasm_std::testing::MockQuerier>, raffle_id: u64) -> bool {
+        TICKET_RANGES.prefix(raffle_id).range(&deps.storage, None, None, Order::Ascending).next().is_some()
+    }
+
+    #[test]
+    fn reindex_backfills_in_batches() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        cw2::set_contract_version(&mut deps.storage, "coreum-raffle", "0.4.0").unwrap();
+        CONFIG.save(&mut deps.storage, &config(250)).unwrap();
+        // 0.4 raffles were stored without any index
+        let unindexed: Map<u64, Raffle> = Map::new("raffles");
+        for id in 1..=3 {
+            let mut raffle = v0_4_raffle(id);
+            if id == 1 {
+                raffle.status = RaffleStatus::Completed;
+                raffle.winners = vec![PrizeWinner { prize: 0, winner: Addr::unchecked("bob"), ticket_index: Some(1) }];
+            }
+            unindexed.save(&mut deps.storage, id, &raffle).unwrap();
+            TICKET_RANGES.save(&mut deps.storage, (id, 0), &TicketRange { owner: Addr::unchecked("alice"), count: 1 }).unwrap();
+            TICKET_RANGES.save(&mut deps.storage, (id, 1), &TicketRange { owner: Addr::unchecked("bob"), count: 2 }).unwrap();
+            USER_TICKET_COUNT.save(&mut deps.storage, (id, &Addr::unchecked("alice")), &1).unwrap();
+            USER_TICKET_COUNT.save(&mut deps.storage, (id, &Addr::unchecked("bob")), &2).unwrap();
+        }
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg { legacy_drand_chain: None }).unwrap();
+        let by_creator = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| RAFFLES.idx.creator
+            .prefix(Addr::unchecked("creator"))
+            .keys(&deps.storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(by_creator(&deps), 0);
+
+        let mut batches = 0;
+        loop {
+            let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), ExecuteMsg::Reindex { limit: Some(2) }).unwrap();
+            batches += 1;
+            if res.attributes.iter().any(|a| a.key == "done" && a.value == "true") { break; }
+        }
+        // 3 raffles, 6 ranges then 6 ticket counts, 2 entries per batch, with a short final batch
+        assert_eq!(batches, 8);
+        assert_eq!(by_creator(&deps), 3);
+        assert_eq!(USER_WINS.load(&deps.storage, (&Addr::unchecked("bob"), 1)).unwrap(), 0);
+        assert!(USER_RAFFLES.has(&deps.storage, (&Addr::unchecked("alice"), 3)));
+        assert_eq!(OWNER_TICKET_RANGES.load(&deps.storage, (2, &Addr::unchecked("bob"), 1)).unwrap(), 2);
+        assert_eq!(RAFFLES.load(&deps.storage, 3).unwrap().protocol_fee_bps, Some(250));
+
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::Reindex { limit: None }).unwrap_err();
+        assert_eq!(err, ContractError::NoReindexPending {});
+    }
+
+    #[test]
+    fn v0_5_contracts_backfill_user_indexes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        cw2::set_contract_version(&mut deps.storage, "coreum-raffle", "0.5.0").unwrap();
+        CONFIG.save(&mut deps.storage, &config(250)).unwrap();
+        // 0.5 indexed raffles but kept no per-user or per-owner entries
+        let mut won = v0_4_raffle(1);
+        won.status = RaffleStatus::Completed;
+        won.winners = vec![PrizeWinner { prize: 0, winner: Addr::unchecked("bob"), ticket_index: Some(1) }];
+        RAFFLES.save(&mut deps.storage, 1, &won).unwrap();
+        TICKET_RANGES.save(&mut deps.storage, (1, 0), &TicketRange { owner: Addr::unchecked("alice"), count: 1 }).unwrap();
+        TICKET_RANGES.save(&mut deps.storage, (1, 1), &TicketRange { owner: Addr::unchecked("bob"), count: 2 }).unwrap();
+        USER_TICKET_COUNT.save(&mut deps.storage, (1, &Addr::unchecked("alice")), &1).unwrap();
+        USER_TICKET_COUNT.save(&mut deps.storage, (1, &Addr::unchecked("bob")), &2).unwrap();
+        // A buyer of a raffle without ticket ranges, e.g. one whose tickets were pruned
+        USER_TICKET_COUNT.save(&mut deps.storage, (2, &Addr::unchecked("carol")), &1).unwrap();
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg { legacy_drand_chain: None }).unwrap();
+        assert_eq!(cw2::get_contract_version(&deps.storage).unwrap().version, "0.6.0");
+        assert!(!USER_RAFFLES.has(&deps.storage, (&Addr::unchecked("alice"), 1)));
+
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::Reindex { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "done" && a.value == "true"));
+        assert!(USER_RAFFLES.has(&deps.storage, (&Addr::unchecked("alice"), 1)));
+        assert!(USER_RAFFLES.has(&deps.storage, (&Addr::unchecked("carol"), 2)));
+        assert_eq!(USER_WINS.load(&deps.storage, (&Addr::unchecked("bob"), 1)).unwrap(), 0);
+        assert_eq!(OWNER_TICKET_RANGES.load(&deps.storage, (1, &Addr::unchecked("bob"), 1)).unwrap(), 2);
+    }
+
+    fn config(protocol_fee_bps: u16) -> Config {
+        Config {
+            admin: Some(Addr::unchecked("admin")),
+            pending_admin: None,
+            protocol_fee_bps,
+            bounty_amount: None,
+            default_drand_chain: None,
+            paused: false,
+        }
+    }
+
+    fn v0_4_raffle(id: u64) -> Raffle {
+        Raffle {
+            id,
+            creator: Addr::unchecked("creator"),
+            prizes: vec![Prize::Nft { contract: Addr::unchecked("collection"), token_id: id.to_string() }],
+            nft_escrow: EscrowStatus::Held,
+            price: Coin::new(100, "ucore"),
+            max_tickets: 10,
+            min_tickets: None,
+            max_tickets_per_wallet: None,
+            max_tickets_per_tx: None,
+            total_sold: 3,
+            start_time: None,
+            end_time: Timestamp::from_seconds(2_000_000_000),
+            revenue_address: Addr::unchecked("creator"),
+            payment_cw20: None,
+            randomness_source: RandomnessSource::Drand,
+            drand_chain: LOE_MAINNET_CHAIN_HASH.to_string(),
+            drand_round: None,
+            status: RaffleStatus::Active,
+            winners: vec![],
+            pot: None,
+            protocol_fee_bps: None,
+            bounty: None,
+        }
+    }
+}
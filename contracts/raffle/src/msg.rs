@@ -1,33 +1,76 @@
     use cosmwasm_schema::{cw_serde, QueryResponses};
-    use cosmwasm_std::{Addr, Coin, Timestamp};
+    use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
     use cw721::Cw721ReceiveMsg;
     use cw20::Cw20ReceiveMsg;
+    use crate::state::{DrandChain, DrandScheme, DrawRecord, EscrowStatus, Pot, Prize, PrizeWinner, OperatorRole, RaffleStatus, RandomnessSource};
 
     #[cw_serde]
     pub struct InstantiateMsg {
         pub admin: Option<String>,
         pub protocol_fee_bps: u16,
         pub bounty_amount: Option<Coin>,
-        pub drand_pubkey: Option<String>,
+        pub drand_chains: Vec<DrandChainMsg>,
+        pub default_drand_chain: Option<String>,
+    }
+
+    #[cw_serde]
+    pub struct DrandChainMsg {
+        pub chain_hash: String,
+        pub pubkey: String,
+        pub genesis_time: u64,
+        pub period: u64,
+        pub scheme: DrandScheme,
+    }
+
+    #[cw_serde]
+    pub struct MigrateMsg {
+        // Chain assigned to raffles created before the registry, defaults to LoE mainnet
+        pub legacy_drand_chain: Option<DrandChainMsg>,
     }
 
     #[cw_serde]
     pub enum ExecuteMsg {
         ReceiveNft(Cw721ReceiveMsg),
         Receive(Cw20ReceiveMsg),
+        // Pulls the NFT into escrow; the contract must be approved for token_id
         CreateRaffle {
             nft_contract: String,
             token_id: String,
             price: Coin,
             max_tickets: u64,
+            min_tickets: Option<u64>,
+            max_tickets_per_wallet: Option<u64>,
+            max_tickets_per_tx: Option<u64>,
             start_time: Option<Timestamp>,
             end_time: Timestamp,
             revenue_address: Option<String>,
             payment_cw20: Option<String>,
+            drand_chain: Option<String>,
+            // Defaults to drand
+            randomness_source: Option<RandomnessSource>,
+        },
+        // Raffles a bundle of native coins, sent as funds with this message
+        CreateCoinRaffle(CreateRaffleNftMsg),
+        // Lottery paying the ticket sales, less fee and bounty, to tiers_bps.len() winners.
+        // Rounds of a series pass undrawn pot on to the next one.
+        CreateLottery {
+            raffle: CreateRaffleNftMsg,
+            tiers_bps: Vec<u16>,
+            series: Option<String>,
         },
+        // Adds the attached native coins as the next prize of an unopened raffle
+        AddCoinPrize { raffle_id: u64 },
+        // Pulls an NFT from the creator as the next prize of an unopened raffle,
+        // which requires a prior cw721 approval
+        AddNftPrize { raffle_id: u64, nft_contract: String, token_id: String },
+        // Pulls cw20 tokens from the creator as the next prize of an unopened raffle,
+        // which requires a prior cw20 allowance
+        AddCw20Prize { raffle_id: u64, token: String, amount: Uint128 },
         BuyTickets {
             raffle_id: u64,
             count: u64,
+            // Mixed into the randomness of commit-reveal raffles
+            entropy: Option<String>,
         },
         EndRaffle {
             raffle_id: u64,
@@ -35,26 +78,55 @@
             drand_round: u64,
             randomness: String,
             signature: String,
+            // required for chained beacons (League of Entropy mainnet)
+            previous_signature: Option<String>,
         },
+        // Settles a commit-reveal raffle, secret is the hex preimage of its commitment
+        RevealRandomness { raffle_id: u64, secret: String },
         CancelRaffle { raffle_id: u64 },
-        UpdateConfig { fee_bps: Option<u16>, bounty: Option<Coin>, drand_pubkey: Option<String>, drand_round_seconds: Option<u64> },
-        WithdrawFees { to: String },
+        // Permissionless close of a raffle that ended with no tickets sold
+        ExpireRaffle { raffle_id: u64 },
+        ClaimRefund { raffle_id: u64 },
+        UpdateConfig { fee_bps: Option<u16>, bounty: Option<Coin>, default_drand_chain: Option<String> },
+        AddDrandChain { chain: DrandChainMsg },
+        SetDrandChainActive { chain_hash: String, active: bool },
+        WithdrawFees { to: String, denoms: Vec<String>, amounts: Vec<Uint128> },
+        // Two-step transfer: the admin proposes, the new admin accepts
+        ProposeAdmin { address: String },
+        AcceptAdmin {},
+        // Drops the admin for good; operators keep their roles
+        RenounceAdmin {},
+        // Grants or revokes an operator role (admin)
+        SetOperator { role: OperatorRole, address: String, enabled: bool },
+        SetPaused { paused: bool },
+        // Runs one batch of the index backfill scheduled by a migration (anyone)
+        Reindex { limit: Option<u32> },
     }
 
+    // Raffle parameters, also the ReceiveNft and cw20 Receive hook payload that
+    // creates a raffle around the sent NFT or tokens
     #[cw_serde]
     pub struct CreateRaffleNftMsg {
         pub price: Coin,
         pub max_tickets: u64,
+        pub min_tickets: Option<u64>,
+        pub max_tickets_per_wallet: Option<u64>,
+        pub max_tickets_per_tx: Option<u64>,
         pub start_time: Option<Timestamp>,
         pub end_time: Timestamp,
         pub revenue_address: Option<String>,
         pub payment_cw20: Option<String>,
+        pub drand_chain: Option<String>,
+        // Defaults to drand
+        pub randomness_source: Option<RandomnessSource>,
     }
 
     #[cw_serde]
     pub struct BuyTicketsCw20Msg {
         pub raffle_id: u64,
-        pub count: u64,
+        // Defaults to as many tickets as the sent amount covers, the rest is refunded
+        pub count: Option<u64>,
+        pub entropy: Option<String>,
     }
 
     #[cw_serde]
@@ -63,13 +135,50 @@
         #[returns(RaffleResponse)]
         Raffle { raffle_id: u64 },
         #[returns(RaffleListResponse)]
-        Raffles { start_after: Option<String>, limit: Option<u32> },
+        Raffles { start_after: Option<String>, limit: Option<u32>, status: Option<RaffleStatus> },
+        #[returns(RaffleListResponse)]
+        RafflesByCreator { creator: String, start_after: Option<String>, limit: Option<u32> },
+        #[returns(RaffleListResponse)]
+        RafflesByStatus { status: RaffleStatus, start_after: Option<String>, limit: Option<u32> },
+        // Raffles with an NFT prize from this collection
+        #[returns(RaffleListResponse)]
+        RafflesByCollection { nft_contract: String, start_after: Option<String>, limit: Option<u32> },
+        // Ordered by end_time, soonest first; start_after is the last raffle id of the previous page
+        #[returns(RaffleListResponse)]
+        RafflesEndingBefore { time: Timestamp, status: Option<RaffleStatus>, start_after: Option<String>, limit: Option<u32> },
         #[returns(ParticipantResponse)]
         Participant { raffle_id: u64, address: String },
         #[returns(ParticipantsResponse)]
         Participants { raffle_id: u64, start_after: Option<String>, limit: Option<u32> },
+        #[returns(TicketOwnerResponse)]
+        TicketOwner { raffle_id: u64, index: u64 },
+        // Ticket index ranges bought by the address, in purchase order
+        #[returns(TicketsOfResponse)]
+        TicketsOf { raffle_id: u64, address: String },
+        // Raffles the address bought tickets in, by raffle id
+        #[returns(UserRafflesResponse)]
+        UserRaffles { address: String, start_after: Option<u64>, limit: Option<u32> },
+        #[returns(UserWinsResponse)]
+        UserWins { address: String },
+        #[returns(RefundResponse)]
+        Refund { raffle_id: u64, address: String },
         #[returns(ConfigResponse)]
         Config {},
+        #[returns(OwnershipResponse)]
+        Ownership {},
+        #[returns(CollectedFeesResponse)]
+        CollectedFees {},
+        #[returns(DrandChainResponse)]
+        DrandChain { chain_hash: String },
+        #[returns(DrandChainsResponse)]
+        DrandChains { start_after: Option<String>, limit: Option<u32> },
+        #[returns(SeriesPotResponse)]
+        SeriesPot { creator: String, series: String },
+        #[returns(DrawResponse)]
+        Draw { raffle_id: u64 },
+        // Re-verifies the stored beacon and re-derives the winners from current ticket state
+        #[returns(VerifyDrawResponse)]
+        VerifyDraw { raffle_id: u64 },
     }
 
     #[cw_serde]
@@ -86,37 +195,181 @@
     pub struct ParticipantResponse {
         pub raffle_id: u64,
         pub address: Addr,
-        pub ticket_count: u32,
+        pub ticket_count: u64,
+        // share of tickets sold, in basis points
+        pub odds_bps: u16,
+        // tickets this address can still buy, within wallet cap and supply
+        pub remaining_allowance: u64,
+    }
+
+    #[cw_serde]
+    pub struct RefundResponse {
+        pub raffle_id: u64,
+        pub address: Addr,
+        pub eligible: bool,
+        pub claimed: bool,
+        pub ticket_count: u64,
+        pub amount: Uint128,
+        // native denom, or the cw20 contract address
+        pub denom: String,
     }
 
     #[cw_serde]
     pub struct RaffleView {
         pub id: u64,
         pub creator: Addr,
-        pub nft_contract: Addr,
-        pub token_id: String,
+        // First NFT prize, kept for clients predating multi-prize raffles
+        pub nft_contract: Option<Addr>,
+        pub token_id: Option<String>,
+        pub prizes: Vec<Prize>,
+        pub nft_escrow: EscrowStatus,
         pub price: Coin,
         pub max_tickets: u64,
+        pub min_tickets: Option<u64>,
+        pub max_tickets_per_wallet: Option<u64>,
+        pub max_tickets_per_tx: Option<u64>,
         pub total_sold: u64,
         pub start_time: Option<Timestamp>,
         pub end_time: Timestamp,
+        pub randomness_source: RandomnessSource,
+        pub drand_chain: String,
+        pub drand_round: Option<u64>,
         pub status: String,
+        // Winner of the first prize
         pub winner: Option<Addr>,
+        // (prize, winner, ticket_index) in prize order
+        pub winners: Vec<(Prize, Addr, Option<u64>)>,
+        pub pot: Option<Pot>,
+        pub protocol_fee_bps: u16,
+        pub bounty: Option<Coin>,
     }
 
     #[cw_serde]
     pub struct ParticipantsResponse {
         pub raffle_id: u64,
-        pub participants: Vec<(Addr, u64)>,
+        // one entry per buyer, ordered by address
+        pub participants: Vec<ParticipantEntry>,
+    }
+
+    #[cw_serde]
+    pub struct ParticipantEntry {
+        pub address: Addr,
+        pub ticket_count: u64,
+        pub odds_bps: u16,
+    }
+
+    #[cw_serde]
+    pub struct TicketOwnerResponse {
+        pub raffle_id: u64,
+        pub index: u64,
+        // None when the ticket hasn't been sold
+        pub owner: Option<Addr>,
+    }
+
+    #[cw_serde]
+    pub struct TicketsOfResponse {
+        pub raffle_id: u64,
+        pub address: Addr,
+        pub ticket_count: u64,
+        pub ranges: Vec<OwnedTicketRange>,
+    }
+
+    #[cw_serde]
+    pub struct OwnedTicketRange {
+        // inclusive
+        pub first_ticket: u64,
+        pub last_ticket: u64,
+    }
+
+    #[cw_serde]
+    pub struct UserRafflesResponse {
+        pub address: Addr,
+        pub raffles: Vec<UserRaffleEntry>,
+    }
+
+    #[cw_serde]
+    pub struct UserRaffleEntry {
+        pub raffle_id: u64,
+        pub ticket_count: u64,
+        pub status: RaffleStatus,
+    }
+
+    #[cw_serde]
+    pub struct UserWinsResponse {
+        pub address: Addr,
+        pub wins: Vec<UserWin>,
+    }
+
+    #[cw_serde]
+    pub struct UserWin {
+        pub raffle_id: u64,
+        pub prize_index: u32,
+        pub prize: Prize,
+        pub ticket_index: Option<u64>,
     }
 
     #[cw_serde]
     pub struct ConfigResponse {
-        pub admin: Addr,
+        pub admin: Option<Addr>,
         pub protocol_fee_bps: u16,
         pub bounty_amount: Option<Coin>,
-        pub drand_pubkey: Option<String>,
-        pub drand_round_seconds: Option<u64>,
+        pub default_drand_chain: Option<String>,
+        pub paused: bool,
+    }
+
+    #[cw_serde]
+    pub struct OwnershipResponse {
+        pub admin: Option<Addr>,
+        pub pending_admin: Option<Addr>,
+        pub operators: Vec<(OperatorRole, Addr)>,
+    }
+
+    #[cw_serde]
+    pub struct CollectedFee {
+        // native denom, or the cw20 contract address
+        pub denom: String,
+        pub cw20: bool,
+        pub amount: Uint128,
+    }
+
+    #[cw_serde]
+    pub struct CollectedFeesResponse {
+        pub fees: Vec<CollectedFee>,
+    }
+
+    #[cw_serde]
+    pub struct DrandChainResponse {
+        pub chain: Option<DrandChain>,
+    }
+
+    #[cw_serde]
+    pub struct DrandChainsResponse {
+        pub chains: Vec<DrandChain>,
+    }
+
+    #[cw_serde]
+    pub struct DrawResponse {
+        // None until the raffle is drawn, and for raffles drawn before records were kept
+        pub draw: Option<DrawRecord>,
+    }
+
+    #[cw_serde]
+    pub struct VerifyDrawResponse {
+        pub raffle_id: u64,
+        // Drand signature checks out against the raffle's chain, or the revealed secret
+        // matches the commitment and reproduces the randomness
+        pub proof_valid: bool,
+        // Recomputed winners equal the stored ones
+        pub winners_match: bool,
+        pub recomputed: Vec<PrizeWinner>,
+    }
+
+    #[cw_serde]
+    pub struct SeriesPotResponse {
+        pub creator: Addr,
+        pub series: String,
+        // Pot waiting for the next round, per native denom or cw20 contract address
+        pub pots: Vec<Coin>,
     }
 
 
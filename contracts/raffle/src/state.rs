@@ -1,31 +1,186 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Timestamp};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Coin, StdError, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[cw_serde]
 pub struct Config {
-    pub admin: Addr,
+    // None once renounced
+    pub admin: Option<Addr>,
+    // Proposed by the admin, takes over on AcceptAdmin
+    pub pending_admin: Option<Addr>,
     pub protocol_fee_bps: u16,
     pub bounty_amount: Option<Coin>,
-    pub drand_pubkey: Option<String>,
-    pub drand_round_seconds: Option<u64>,
+    // Chain used by raffles that don't pick one at creation
+    pub default_drand_chain: Option<String>,
+    // Blocks new raffles and ticket sales; ending, refunds and withdrawals still work
+    #[serde(default)]
+    pub paused: bool,
+}
+
+// Operators act on the admin's behalf for one area each
+#[cw_serde]
+pub enum OperatorRole {
+    // SetPaused
+    Pauser,
+    // protocol fee and bounty in UpdateConfig
+    FeeManager,
+    // AddDrandChain, SetDrandChainActive and the default chain in UpdateConfig
+    BeaconManager,
+}
+
+impl OperatorRole {
+    pub fn key(&self) -> &'static str {
+        match self {
+            OperatorRole::Pauser => "pauser",
+            OperatorRole::FeeManager => "fee_manager",
+            OperatorRole::BeaconManager => "beacon_manager",
+        }
+    }
+}
+
+#[cw_serde]
+pub enum DrandScheme {
+    // pedersen-bls-chained: G1 key, G2 sig over previous_signature || round
+    Chained,
+    // pedersen-bls-unchained: G1 key, G2 sig over round
+    Unchained,
+    // bls-unchained-g1-rfc9380 (quicknet): G2 key, G1 sig over round
+    UnchainedG1,
+}
+
+#[cw_serde]
+pub struct DrandChain {
+    pub chain_hash: String,
+    pub pubkey: String,
+    pub genesis_time: u64,
+    pub period: u64,
+    pub scheme: DrandScheme,
+    // Inactive chains can't be picked by new raffles but still settle pinned ones
+    pub active: bool,
 }
 
 #[cw_serde]
 pub struct Raffle {
     pub id: u64,
     pub creator: Addr,
-    pub nft_contract: Addr,
-    pub token_id: String,
+    // Awarded in order: prizes[0] goes to the first ticket drawn
+    pub prizes: Vec<Prize>,
+    pub nft_escrow: EscrowStatus,
     pub price: Coin,
     pub max_tickets: u64,
+    // Draw only if at least this many tickets sell, otherwise the raffle fails
+    pub min_tickets: Option<u64>,
+    pub max_tickets_per_wallet: Option<u64>,
+    pub max_tickets_per_tx: Option<u64>,
     pub total_sold: u64,
     pub start_time: Option<Timestamp>,
     pub end_time: Timestamp,
     pub revenue_address: Addr,
     pub payment_cw20: Option<Addr>,
+    // Raffles stored before commit-reveal existed are all drand
+    #[serde(default)]
+    pub randomness_source: RandomnessSource,
+    // Empty for commit-reveal raffles
+    pub drand_chain: String,
+    // Round that must settle the raffle, fixed when it sells out
+    pub drand_round: Option<u64>,
     pub status: RaffleStatus,
-    pub winner: Option<Addr>,
+    pub winners: Vec<PrizeWinner>,
+    // Set for lotteries, whose ticket sales are paid out as the prizes
+    pub pot: Option<Pot>,
+    // Protocol fee when the raffle was created, so later config changes don't reach
+    // raffles in flight. Unset on raffles from before 0.6.0 until Reindex pins the fee
+    // then in effect; until then they pay the config fee.
+    pub protocol_fee_bps: Option<u16>,
+    // Executor bounty when the raffle was created, pinned like the fee. Raffles without
+    // a pinned fee are from before 0.6.0 and pay the config bounty instead.
+    pub bounty: Option<Coin>,
+}
+
+#[cw_serde]
+pub struct Pot {
+    // Share of the pot per winner in draw order, in bps summing to 10000
+    pub tiers_bps: Vec<u16>,
+    // Series of the creator's lotteries this round belongs to, an undrawn pot
+    // carries over to the next round created in it
+    pub series: Option<String>,
+    // Carried over from the series when this round was created
+    pub rollover: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum RandomnessSource {
+    // Settled by EndRaffle with a beacon from the raffle's drand chain
+    #[default]
+    Drand,
+    // Settled by the creator revealing the secret behind `commitment`, mixed with the
+    // entropy buyers contributed. Past end_time + reveal_timeout seconds buyers get refunds.
+    // The creator and revenue address can't buy tickets, but the creator can still withhold
+    // the reveal when the outcome doesn't suit them, which only ends in refunds.
+    CommitReveal { commitment: String, reveal_timeout: u64 },
+}
+
+#[cw_serde]
+pub enum Prize {
+    Nft { contract: Addr, token_id: String },
+    // Native coins sent with the creating or adding message
+    Coins { amount: Vec<Coin> },
+    // cw20 tokens sent through the Receive hook
+    Cw20 { contract: Addr, amount: Uint128 },
+}
+
+#[cw_serde]
+pub struct PrizeWinner {
+    // Index into Raffle::prizes
+    pub prize: u32,
+    pub winner: Addr,
+    // None for raffles drawn before 0.4.0, which didn't record it
+    pub ticket_index: Option<u64>,
+}
+
+// Everything needed to re-check a settled raffle's draw
+#[cw_serde]
+pub struct DrawRecord {
+    pub raffle_id: u64,
+    pub proof: DrawProof,
+    // Hex randomness the winners were derived from
+    pub randomness: String,
+    // Tickets sold when the draw ran, the modulus of every winner derivation
+    pub total_sold: u64,
+    pub winners: Vec<PrizeWinner>,
+    pub drawn_at: Timestamp,
+}
+
+#[cw_serde]
+pub enum DrawProof {
+    Drand { chain: String, round: u64, signature: String, previous_signature: Option<String> },
+    // randomness = sha256(secret || entropy)
+    CommitReveal { secret: String, entropy: String },
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct Reindex {
+    // Pre-0.3 per-ticket entries are converted first, then raffles re-indexed by id,
+    // then ticket ranges by key, then per-buyer ticket counts by key
+    pub tickets_done: bool,
+    pub raffle_after: Option<u64>,
+    pub raffles_done: bool,
+    pub range_after: Option<(u64, u64)>,
+    pub ranges_done: bool,
+    pub entrant_after: Option<(u64, Addr)>,
+}
+
+#[cw_serde]
+pub enum EscrowStatus {
+    // Prizes are held by this contract
+    Held,
+    // Prizes have left the contract (awarded or returned)
+    Released,
+    // Never held: 0.1 direct CreateRaffle left the NFT with the creator, who hands it
+    // over to the winner themselves
+    NotEscrowed,
 }
 
 #[cw_serde]
@@ -33,16 +188,133 @@ pub enum RaffleStatus {
     Active,
     Completed,
     Cancelled,
+    // Ended with no tickets sold, NFT returned to the creator
+    Expired,
+    // Ended below min_tickets, NFT returned and buyers refunded
+    Failed,
+}
+
+#[cw_serde]
+pub struct TicketRange {
+    pub owner: Addr,
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct FeeBalance {
+    // denom is a cw20 contract address rather than a native denom
+    pub cw20: bool,
+    pub amount: Uint128,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const NEXT_ID: Item<u64> = Item::new("next_id");
-pub const RAFFLES: Map<u64, Raffle> = Map::new("raffles");
+// (role key, operator) -> granted
+pub const OPERATORS: Map<(&str, &Addr), bool> = Map::new("operators");
+pub const RAFFLES: IndexedMap<u64, Raffle, RaffleIndexes> = IndexedMap::new("raffles", RaffleIndexes {
+    creator: MultiIndex::new(|_pk, r| r.creator.clone(), "raffles", "raffles__creator"),
+    status: MultiIndex::new(|_pk, r| status_key(&r.status), "raffles", "raffles__status"),
+    collections: CollectionIndex { entries: Map::new("raffles__collections") },
+    end_time: MultiIndex::new(|_pk, r| r.end_time.nanos(), "raffles", "raffles__end_time"),
+    status_end_time: MultiIndex::new(|_pk, r| (status_key(&r.status), r.end_time.nanos()), "raffles", "raffles__status_end_time"),
+});
+
+pub struct RaffleIndexes<'a> {
+    pub creator: MultiIndex<'a, Addr, Raffle, u64>,
+    pub status: MultiIndex<'a, u8, Raffle, u64>,
+    pub collections: CollectionIndex<'a>,
+    // end_time in nanoseconds
+    pub end_time: MultiIndex<'a, u64, Raffle, u64>,
+    // (status, end_time in nanoseconds)
+    pub status_end_time: MultiIndex<'a, (u8, u64), Raffle, u64>,
+}
+
+impl<'a> IndexList<Raffle> for RaffleIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Raffle>> + '_> {
+        let v: Vec<&dyn Index<Raffle>> = vec![&self.creator, &self.status, &self.collections, &self.end_time, &self.status_end_time];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn status_key(status: &RaffleStatus) -> u8 {
+    match status {
+        RaffleStatus::Active => 0,
+        RaffleStatus::Completed => 1,
+        RaffleStatus::Cancelled => 2,
+        RaffleStatus::Expired => 3,
+        RaffleStatus::Failed => 4,
+    }
+}
+
+/// Indexes a raffle once under each NFT collection among its prizes, which a
+/// MultiIndex can't do as it takes a single key per raffle
+pub struct CollectionIndex<'a> {
+    // (collection, raffle_id) -> true
+    pub entries: Map<'a, (&'a str, u64), bool>,
+}
+
+impl<'a> Index<Raffle> for CollectionIndex<'a> {
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &Raffle) -> StdResult<()> {
+        let id = raffle_id_from_pk(pk)?;
+        for collection in collections(data) {
+            self.entries.save(store, (collection.as_str(), id), &true)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &Raffle) -> StdResult<()> {
+        let id = raffle_id_from_pk(pk)?;
+        for collection in collections(old_data) {
+            self.entries.remove(store, (collection.as_str(), id));
+        }
+        Ok(())
+    }
+}
+
+fn raffle_id_from_pk(pk: &[u8]) -> StdResult<u64> {
+    let bytes: [u8; 8] = pk.try_into().map_err(|_| StdError::generic_err("invalid raffle key"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Distinct contracts of a raffle's NFT prizes
+pub fn collections(raffle: &Raffle) -> Vec<String> {
+    let mut contracts: Vec<String> = raffle.prizes
+        .iter()
+        .filter_map(|p| match p {
+            Prize::Nft { contract, .. } => Some(contract.to_string()),
+            _ => None,
+        })
+        .collect();
+    contracts.sort();
+    contracts.dedup();
+    contracts
+}
+
+// Chain hash -> drand beacon chain
+pub const DRAND_CHAINS: Map<&str, DrandChain> = Map::new("drand_chains");
 
-// Ticket index -> owner
-pub const TICKETS: Map<(u64, u64), Addr> = Map::new("tickets");
+// (raffle_id, first ticket index) -> tickets bought in one purchase
+pub const TICKET_RANGES: Map<(u64, u64), TicketRange> = Map::new("ticket_ranges");
+// (raffle_id, owner, first ticket index) -> count, TICKET_RANGES grouped by owner
+pub const OWNER_TICKET_RANGES: Map<(u64, &Addr, u64), u64> = Map::new("owner_ticket_ranges");
 // User ticket counts
 pub const USER_TICKET_COUNT: Map<(u64, &Addr), u64> = Map::new("user_ticket_count");
+// (buyer, raffle_id) -> entered, the reverse of USER_TICKET_COUNT
+pub const USER_RAFFLES: Map<(&Addr, u64), bool> = Map::new("user_raffles");
+// (winner, raffle_id) -> index of the prize won
+pub const USER_WINS: Map<(&Addr, u64), u32> = Map::new("user_wins");
+// Refunds already paid out for cancelled raffles
+pub const REFUNDS_CLAIMED: Map<(u64, &Addr), bool> = Map::new("refunds_claimed");
+// Protocol fees held by the contract, by native denom or cw20 contract address
+pub const COLLECTED_FEES: Map<&str, FeeBalance> = Map::new("collected_fees");
+// (creator, series, payment denom) -> pot waiting for the series' next lottery round
+pub const SERIES_POTS: Map<(&Addr, &str, &str), Uint128> = Map::new("series_pots");
+// Buyer entropy of commit-reveal raffles, a hash chain over their purchases
+pub const ENTROPY: Map<u64, Binary> = Map::new("entropy");
+// Draw inputs and results of completed raffles
+pub const DRAWS: Map<u64, DrawRecord> = Map::new("draws");
+// Progress of the index backfill a migration scheduled, removed once complete
+pub const REINDEX: Item<Reindex> = Item::new("reindex");
 // Used drand rounds to prevent replays
 pub const USED_ROUNDS: Map<(u64, u64), bool> = Map::new("used_rounds");
 
@@ -121,15 +121,17 @@ export class RaffleContractService {
    * @param drandRound - Drand round for randomness
    * @param randomness - Drand randomness value
    * @param signature - Drand signature
+   * @param previousSignature - Previous round signature (chained beacons)
    */
-  async endRaffle(raffleId: number, drandRound: number, randomness: string, signature: string): Promise<string> {
+  async endRaffle(raffleId: number, drandRound: number, randomness: string, signature: string, previousSignature?: string): Promise<string> {
     try {
       const msg = {
         end_raffle: {
           raffle_id: raffleId,
           drand_round: drandRound,
           randomness,
-          signature
+          signature,
+          previous_signature: previousSignature ?? null
         }
       };
 
@@ -167,6 +169,28 @@ export class RaffleContractService {
     }
   }
 
+  /**
+   * Add an NFT as the next prize of a raffle that hasn't opened yet (creator only)
+   * @param raffleId - Raffle ID
+   * @param nftContract - CW721 contract of the prize
+   * @param tokenId - Token ID of the prize
+   * @returns Transaction hash
+   */
+  async addPrize(raffleId: number, nftContract: string, tokenId: string): Promise<string> {
+    try {
+      return await this.leapWallet.sendNFT(
+        nftContract,
+        tokenId,
+        this.RAFFLE_CONTRACT_ADDRESS,
+        { raffle_id: raffleId }
+      );
+
+    } catch (error) {
+      console.error('❌ Failed to add prize:', error);
+      throw new Error(`Failed to add prize: ${error instanceof Error ? error.message : 'Unknown error'}`);
+    }
+  }
+
 
   /**
    * Validate raffle creation parameters